@@ -1,26 +1,383 @@
-use playwright_rs::{Playwright, protocol::page::{GotoOptions, WaitUntil}};
+use playwright_rs::{Playwright, api::{LaunchOptions, LaunchPersistentContextOptions, ProxySettings}, protocol::{Browser, BrowserContext, BrowserContextOptions, Cookie, HttpCredentials, Page, Viewport, page::{GotoOptions, PdfOptions, WaitUntil}}};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use crate::constants::load_js_script;
-use crate::models::{Link, SearchResult};
-use readability_rust::{Readability, ReadabilityOptions};
-use crate::extractor;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use scraper::Selector;
+use url::Url;
+use crate::cache::{PageCache, RecrawlCache, RecrawlEntry, SearchCache};
+use crate::config::BrowserConfig;
+use crate::error::DocserError;
+use crate::i18n;
+use crate::metrics::Metrics;
+use crate::constants::{COMPOSED_HTML_MAX_DEPTH, COMPOSED_HTML_MAX_NODES, COMPOSED_HTML_TRUNCATED, DEFAULT_READY_SELECTORS, EXPAND_COLLAPSED_JS, SCROLL_TO_BOTTOM_JS, js_string, load_js_script};
+use crate::devices::Device;
+use crate::ratelimit::HostRateLimiter;
+use crate::robots::RobotsCache;
+use crate::models::{Capabilities, ExtractionStrategy, HealthStatus, Link, OutputFormat, PageMetadata, PaperFormat, ResponseInfo, ScrapeOptions, ScrapedPage, SearchResult, SearchSiteInfo, SitePage, Stats, WaitStrategy};
+use crate::search::{SEARCH_SITES, SearchSite};
+use crate::sitemap::{self, SitemapEntry};
+use crate::extractor::{self, ExtractionTier, MarkdownConverter};
+use crate::urls::{PathFilter, is_asset_url, normalize_url, redact_credentials, take_credentials};
+use tracing::{debug, error, info, warn};
+
+// Flags that keep Chromium stable in containers and stop it throttling background pages
+const CHROMIUM_ARGS: &[&str] = &[
+    "--no-sandbox",
+    "--disable-setuid-sandbox",
+    "--disable-dev-shm-usage",
+    "--disable-web-security",
+    "--disable-background-timer-throttling",
+    "--disable-renderer-backgrounding",
+    "--disable-backgrounding-occluded-windows",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrowserEngine {
+    Chromium,
+    Firefox,
+    #[default]
+    Webkit,
+}
+
+impl BrowserEngine {
+    pub const ALL: &[BrowserEngine] = &[BrowserEngine::Chromium, BrowserEngine::Firefox, BrowserEngine::Webkit];
+}
+
+impl FromStr for BrowserEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "chromium" | "chrome" => Ok(Self::Chromium),
+            "firefox" => Ok(Self::Firefox),
+            "webkit" => Ok(Self::Webkit),
+            other => Err(format!("Unknown browser engine '{}', expected chromium, firefox or webkit", other)),
+        }
+    }
+}
+
+impl fmt::Display for BrowserEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Chromium => "chromium",
+            Self::Firefox => "firefox",
+            Self::Webkit => "webkit",
+        };
+        f.write_str(name)
+    }
+}
+
+// Resource types aborted by block_resources. Nothing that can carry content (documents, scripts,
+// XHR/fetch) is listed, so client-rendered pages still render.
+const BLOCKED_RESOURCE_TYPES: &[&str] = &["image", "media", "font"];
+
+// Pause after each auto-scroll step when the request doesn't set scroll_delay_ms
+const DEFAULT_SCROLL_DELAY_MS: u64 = 500;
+
+// How often readiness indicators are re-evaluated while waiting for a page to render
+const READY_CHECK_INTERVAL_MS: u64 = 250;
+
+// Pages remembered (markdown and validators only) for incremental sitemap re-crawls
+const RECRAWL_CACHE_MAX_ENTRIES: usize = 10_000;
+
+// Pause after expanding accordions, for content rendered in response to the clicks
+const EXPAND_SETTLE_MS: u64 = 500;
+
+// Content types returned as-is instead of going through extraction
+const TEXT_CONTENT_TYPES: &[&str] = &["text/plain", "text/markdown", "text/x-markdown"];
+
+// Content types (and prefixes) of binary documents that have no text to convert
+const BINARY_CONTENT_TYPES: &[&str] = &[
+    "application/pdf", "application/octet-stream", "application/zip", "image/", "audio/", "video/", "font/",
+];
+
+enum ContentKind {
+    Extractable,
+    Text,
+    Json,
+    Binary(String),
+    Unsupported(String),
+}
+
+// Where navigate() left the page: the final response, and the canonical URL when it was followed
+struct Navigation {
+    response: ResponseInfo,
+    canonical_url: Option<String>,
+}
+
+// A page in its own context, holding one of the pool's slots until closed. Call close() when
+// done; a lease dropped without it (a cancelled request, a panic) closes itself in the background.
+struct PageLease {
+    context: Option<BrowserContext>,
+    page: Page,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PageLease {
+    async fn close(mut self) {
+        match self.context.take() {
+            Some(context) => close_context(context).await,
+            // A page of the shared persistent context; the context itself stays open
+            None => close_page(&self.page).await,
+        }
+        // The slot is released only once the context is gone, so the pool size stays a real cap
+        self.permit.take();
+    }
+}
+
+impl Drop for PageLease {
+    fn drop(&mut self) {
+        // close() already ran
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        let context = self.context.take();
+        let page = self.page.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                debug!("Page lease dropped without close, closing it in the background");
+                handle.spawn(async move {
+                    match context {
+                        Some(context) => close_context(context).await,
+                        None => close_page(&page).await,
+                    }
+                    drop(permit);
+                });
+            }
+            Err(_) => warn!("Page lease dropped outside the runtime, its page was not closed"),
+        }
+    }
+}
+
+async fn close_context(context: BrowserContext) {
+    if let Err(e) = context.close().await {
+        warn!("Failed to close browser context: {}", e);
+    }
+}
+
+async fn close_page(page: &Page) {
+    if let Err(e) = page.close().await {
+        warn!("Failed to close page: {}", e);
+    }
+}
 
 #[derive(Clone)]
 pub struct BrowserManager {
     instance: Arc<Mutex<Option<Arc<Playwright>>>>,
+    browser: Arc<Mutex<Option<Browser>>>,
+    // The single persistent context used instead of the browser when user_data_dir is set
+    persistent_context: Arc<Mutex<Option<BrowserContext>>>,
+    context_slots: Arc<Semaphore>,
+    in_flight: Arc<Semaphore>,
+    config: Arc<BrowserConfig>,
+    search_cache: Option<Arc<SearchCache>>,
+    page_cache: Arc<PageCache>,
+    recrawl_cache: Option<Arc<RecrawlCache>>,
+    metrics: Arc<Metrics>,
+    rate_limiter: Arc<HostRateLimiter>,
+    robots: Arc<RobotsCache>,
+    // Plain HTTP client for robots.txt and sitemaps, behind the same proxy as the browser
+    http: reqwest::Client,
 }
 
 impl BrowserManager {
-    pub async fn new() -> Self {
-        let playwright = Playwright::launch().await.ok().map(Arc::new);
-        Self {
+    pub async fn try_new() -> Result<Self, DocserError> {
+        Self::try_with_config(BrowserConfig::from_env()).await
+    }
+
+    // A Playwright launch failure is logged and retried lazily on first use, unless fail_fast
+    // is set, in which case it (or a browser that won't start) is returned as an error
+    pub async fn try_with_config(config: BrowserConfig) -> Result<Self, DocserError> {
+        let playwright = match Playwright::launch().await {
+            Ok(playwright) => Some(Arc::new(playwright)),
+            Err(e) if config.fail_fast => {
+                return Err(DocserError::Browser(format!("Failed to launch Playwright: {}", e)));
+            }
+            Err(e) => {
+                error!(
+                    "Failed to launch Playwright, will retry on first use: {} \
+                     (are the driver and browsers installed? try `npx playwright install {}`)",
+                    e, config.engine
+                );
+                None
+            }
+        };
+        let fail_fast = config.fail_fast;
+
+        let search_cache = match SearchCache::open(
+            &config.search_cache_path,
+            config.search_cache_ttl,
+            config.search_cache_max_rows,
+        ) {
+            Ok(cache) => Some(Arc::new(cache)),
+            Err(e) => {
+                warn!(
+                    "Search cache disabled, failed to open {}: {}",
+                    config.search_cache_path.display(),
+                    e
+                );
+                None
+            }
+        };
+
+        if let Some(path) = &config.framework_profiles_path {
+            match extractor::load_framework_profiles(path) {
+                Ok(count) => info!("Loaded {} framework profiles from {}", count, path.display()),
+                Err(e) => warn!("Failed to load framework profiles from {}: {}", path.display(), e),
+            }
+        }
+
+        // Recrawl records share the search cache's database file, in a table of their own
+        let recrawl_cache = match RecrawlCache::open(&config.search_cache_path, RECRAWL_CACHE_MAX_ENTRIES) {
+            Ok(cache) => Some(Arc::new(cache)),
+            Err(e) => {
+                warn!(
+                    "Incremental sitemap crawls disabled, failed to open {}: {}",
+                    config.search_cache_path.display(),
+                    e
+                );
+                None
+            }
+        };
+
+        let page_cache = Arc::new(PageCache::new(config.page_cache_max_entries, config.page_cache_ttl));
+
+        let mut http = reqwest::Client::builder().user_agent(concat!("docser/", env!("CARGO_PKG_VERSION")));
+        if let Some(proxy) = &config.proxy {
+            match reqwest::Proxy::all(&proxy.server) {
+                Ok(mut http_proxy) => {
+                    if let Some(username) = &proxy.username {
+                        http_proxy = http_proxy.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+                    }
+                    // Same bypass list as the browser, so robots.txt and sitemaps of internal hosts go direct
+                    if let Some(bypass) = &proxy.bypass {
+                        http_proxy = http_proxy.no_proxy(reqwest::NoProxy::from_string(bypass));
+                    }
+                    http = http.proxy(http_proxy);
+                }
+                Err(e) => warn!("Invalid proxy URL {}: {}", redact_credentials(&proxy.server), e),
+            }
+        }
+        let http = http.build().unwrap_or_default();
+        let rate_limiter = Arc::new(HostRateLimiter::new(config.host_requests_per_second));
+
+        let manager = Self {
             instance: Arc::new(Mutex::new(playwright)),
+            browser: Arc::new(Mutex::new(None)),
+            persistent_context: Arc::new(Mutex::new(None)),
+            context_slots: Arc::new(Semaphore::new(config.context_pool_size.max(1))),
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight.max(1))),
+            config: Arc::new(config),
+            search_cache,
+            page_cache,
+            recrawl_cache,
+            metrics: Arc::new(Metrics::new()),
+            rate_limiter,
+            robots: Arc::new(RobotsCache::new(http.clone())),
+            http,
+        };
+
+        if fail_fast {
+            // Launch the browser now so missing binaries surface at startup, not on the first crawl
+            manager.get_browser().await?;
+        }
+        Ok(manager)
+    }
+
+    fn launch_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        if self.config.engine == BrowserEngine::Chromium {
+            args.extend(CHROMIUM_ARGS.iter().map(|arg| arg.to_string()));
+        }
+        args.extend(self.config.extra_launch_args.iter().cloned());
+        args
+    }
+
+    fn proxy_settings(&self) -> Option<ProxySettings> {
+        self.config.proxy.as_ref().map(|proxy| {
+            debug!("Routing browser traffic through proxy {}", redact_credentials(&proxy.server));
+            ProxySettings {
+                server: proxy.server.clone(),
+                bypass: proxy.bypass.clone(),
+                username: proxy.username.clone(),
+                password: proxy.password.clone(),
+            }
+        })
+    }
+
+    async fn launch_browser(&self, playwright: &Playwright) -> Result<Browser, DocserError> {
+        let args = self.launch_args();
+        debug!("Launching {} browser with args {:?}", self.config.engine, args);
+        let browser_type = match self.config.engine {
+            BrowserEngine::Chromium => playwright.chromium(),
+            BrowserEngine::Firefox => playwright.firefox(),
+            BrowserEngine::Webkit => playwright.webkit(),
+        };
+        let mut launch_options = LaunchOptions::new().args(args);
+        if let Some(proxy) = self.proxy_settings() {
+            launch_options = launch_options.proxy(proxy);
+        }
+        let browser = browser_type.launch_with_options(launch_options).await?;
+        Ok(browser)
+    }
+
+    // The persistent context keeps cookies, localStorage and the rest of the profile in
+    // user_data_dir, so sessions (a solved captcha, a login) survive restarts
+    async fn get_persistent_context(&self, user_data_dir: &Path) -> Result<BrowserContext, DocserError> {
+        let mut context_lock = self.persistent_context.lock().await;
+        if let Some(context) = context_lock.as_ref() {
+            return Ok(context.clone());
+        }
+
+        let playwright = self.get_playwright().await?;
+        let browser_type = match self.config.engine {
+            BrowserEngine::Chromium => playwright.chromium(),
+            BrowserEngine::Firefox => playwright.firefox(),
+            BrowserEngine::Webkit => playwright.webkit(),
+        };
+        info!("Launching persistent {} context in {}", self.config.engine, user_data_dir.display());
+        let (width, height) = self.config.viewport;
+        let mut launch_options = LaunchPersistentContextOptions::new()
+            .args(self.launch_args())
+            .viewport(Viewport { width: width as i32, height: height as i32 });
+        if let Some(proxy) = self.proxy_settings() {
+            launch_options = launch_options.proxy(proxy);
+        }
+        let context = browser_type
+            .launch_persistent_context_with_options(user_data_dir.to_string_lossy().into_owned(), launch_options)
+            .await?;
+        *context_lock = Some(context.clone());
+        Ok(context)
+    }
+
+    // Decide how to handle a response based on its Content-Type header.
+    // A missing header is treated as HTML, which is what browsers assume too.
+    fn classify_content_type(&self, content_type: Option<&str>) -> ContentKind {
+        let Some(content_type) = content_type else {
+            return ContentKind::Extractable;
+        };
+        let essence = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        if self.config.extractable_content_types.iter().any(|t| *t == essence) {
+            ContentKind::Extractable
+        } else if TEXT_CONTENT_TYPES.contains(&essence.as_str()) {
+            ContentKind::Text
+        } else if essence == "application/json" || essence.ends_with("+json") {
+            ContentKind::Json
+        } else if BINARY_CONTENT_TYPES.iter().any(|t| essence.starts_with(t)) {
+            ContentKind::Binary(essence)
+        } else {
+            ContentKind::Unsupported(essence)
         }
     }
 
     // Helper to get or launch playwright
-    async fn get_playwright(&self) -> Result<Arc<Playwright>, Box<dyn std::error::Error + Send + Sync>> {
+    async fn get_playwright(&self) -> Result<Arc<Playwright>, DocserError> {
         let mut pw_lock = self.instance.lock().await;
         if let Some(ref pw) = *pw_lock {
             Ok(pw.clone())
@@ -31,226 +388,1226 @@ impl BrowserManager {
         }
     }
 
-    pub async fn scrape_page(&self, url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    // Returns the shared browser, launching it on first use or after it disconnected
+    async fn get_browser(&self) -> Result<Browser, DocserError> {
+        let mut browser_lock = self.browser.lock().await;
+        if let Some(browser) = browser_lock.as_ref() {
+            if browser.is_connected() {
+                return Ok(browser.clone());
+            }
+            warn!("Browser disconnected, relaunching");
+        }
         let playwright = self.get_playwright().await?;
+        let browser = self.launch_browser(&playwright).await?;
+        *browser_lock = Some(browser.clone());
+        Ok(browser)
+    }
 
-        let _args = vec![
-            "--no-sandbox".to_string(),
-            "--disable-setuid-sandbox".to_string(),
-            "--disable-dev-shm-usage".to_string(),
-            "--disable-web-security".to_string(),
-            "--disable-background-timer-throttling".to_string(),
-            "--disable-renderer-backgrounding".to_string(),
-            "--disable-backgrounding-occluded-windows".to_string(),
-        ];
+    // Closes the shared browser and stops the Playwright driver. Called once on exit; Drop can't
+    // run the async close, and an unclosed browser outlives the server as an orphaned process.
+    pub async fn shutdown(&self) {
+        if let Some(context) = self.persistent_context.lock().await.take() {
+            info!("Closing persistent browser context");
+            close_context(context).await;
+        }
+        if let Some(browser) = self.browser.lock().await.take() {
+            info!("Closing browser");
+            if let Err(e) = browser.close().await {
+                warn!("Failed to close browser on shutdown: {}", e);
+            }
+        }
+        self.instance.lock().await.take();
+    }
 
-        let browser = playwright.webkit().launch().await?;
+    // Drops the shared browser so the next call launches a fresh one
+    async fn recycle_browser(&self) {
+        let browser = self.browser.lock().await.take();
+        if let Some(browser) = browser {
+            if let Err(e) = browser.close().await {
+                warn!("Failed to close recycled browser: {}", e);
+            }
+        }
+    }
 
-        let page = browser.new_page().await?;
+    fn normalize_url(&self, url: &str) -> String {
+        normalize_url(url, &self.config.tracking_params)
+    }
 
-        let response = page
-            .goto(
-                url,
-                Some(
-                    GotoOptions::new()
-                        .wait_until(WaitUntil::DomContentLoaded)
-                        .timeout(std::time::Duration::from_secs(30)),
-                ),
-            )
-            .await?
-            .expect("URL should return a response");
-        if !response.ok() {
-            return Err(format!("HTTP error: {}", response.status()).into());
+    // The URL actually navigated to: the lang version of the page on sites with language-specific
+    // URLs, and with user:pass@ moved into the options, so the credentials reach the context as
+    // HTTP credentials and never the address bar, the logs or the page cache key's URL part
+    fn prepare_target<'a>(&self, url: &str, options: &'a ScrapeOptions) -> (String, Cow<'a, ScrapeOptions>) {
+        let localized = options.lang.as_deref().and_then(|lang| i18n::localize_url(url, lang));
+        let (target, credentials) = take_credentials(localized.as_deref().unwrap_or(url));
+        if localized.is_some() {
+            debug!("Requesting {} for lang {}", target, options.lang.as_deref().unwrap_or_default());
+        }
+        match credentials {
+            Some((username, password)) => {
+                let mut options = options.clone();
+                options.username = Some(username);
+                options.password = Some(password);
+                (target, Cow::Owned(options))
+            }
+            None => (target, Cow::Borrowed(options)),
         }
+    }
+
+    fn context_options(&self, options: &ScrapeOptions) -> Result<BrowserContextOptions, DocserError> {
+        let mut builder = BrowserContextOptions::builder();
 
-        // Smart waiting for SPA content: wait for Angular/React/Vue app to be ready
-        // Check for framework-specific indicators or content elements
-        let ready_indicators = vec![
-            "document.querySelector('app-post')",     // Angular component
-            "document.querySelector('[ng-version]')", // Angular app
-            "document.querySelector('#root, #app, #__next, #vue-app')", // React/Vue roots
-            "document.querySelector('main, article, .post-content, .article-content, .content')", // Content areas
-        ];
+        let device = match &options.device {
+            Some(name) => Some(Device::find(name).ok_or_else(|| {
+                DocserError::InvalidArgument(format!(
+                    "Unknown device '{}', expected one of: {}",
+                    name,
+                    Device::names().join(", ")
+                ))
+            })?),
+            None => None,
+        };
+        let (default_width, default_height) = self.config.viewport;
+        let (width, height, scale) = match (options.viewport, device) {
+            (Some(viewport), _) => (viewport.width, viewport.height, viewport.device_scale_factor.unwrap_or(1.0)),
+            (None, Some(device)) => (device.width, device.height, device.device_scale_factor),
+            (None, None) => (default_width, default_height, 1.0),
+        };
+        builder = builder
+            .viewport(Viewport { width: width as i32, height: height as i32 })
+            .device_scale_factor(scale);
+        if let Some(device) = device {
+            builder = builder.is_mobile(device.is_mobile).has_touch(device.is_mobile);
+        }
 
-        let max_wait_ms = 15000; // 15 seconds for heavy SPAs
-        let check_interval_ms = 250; // check every 250ms
-        let mut page_ready = false;
+        let user_agent = options
+            .user_agent
+            .clone()
+            .or_else(|| device.and_then(|device| device.user_agent).map(str::to_string));
+        if let Some(user_agent) = user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(locale) = options.locale.as_ref().or(self.config.locale.as_ref()) {
+            builder = builder.locale(locale.clone());
+        }
+        if let Some(timezone_id) = options.timezone_id.as_ref().or(self.config.timezone_id.as_ref()) {
+            builder = builder.timezone_id(timezone_id.clone());
+        }
+        let mut headers = options.headers.clone().unwrap_or_default();
+        if let Some(lang) = &options.lang
+            && !headers.keys().any(|name| name.eq_ignore_ascii_case("accept-language"))
+        {
+            headers.insert("Accept-Language".to_string(), lang.clone());
+        }
+        if !headers.is_empty() {
+            builder = builder.extra_http_headers(headers.into_iter().collect());
+        }
+        // Cookies and localStorage saved from a logged-in session, for docs behind SSO
+        let storage_state = options
+            .storage_state
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| self.config.storage_state_path.clone());
+        if let Some(path) = storage_state {
+            if !path.is_file() {
+                return Err(DocserError::InvalidArgument(format!(
+                    "Storage state file {} does not exist",
+                    path.display()
+                )));
+            }
+            builder = builder.storage_state_path(path.to_string_lossy().into_owned());
+        }
+        if let Some(username) = &options.username {
+            builder = builder.http_credentials(HttpCredentials {
+                username: username.clone(),
+                password: options.password.clone().unwrap_or_default(),
+            });
+        }
+        Ok(builder.build())
+    }
 
-        for attempt in 0..(max_wait_ms / check_interval_ms) {
-            let mut ready = false;
+    // Caps whole operations (a scrape with its retries, a search, a PDF render) so bursts of tool
+    // calls queue up instead of piling browser work onto the host. Cache hits never take a permit.
+    async fn acquire_in_flight(&self, target: &str) -> Result<OwnedSemaphorePermit, DocserError> {
+        if let Ok(permit) = self.in_flight.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        info!(
+            "{} operations already in flight, {} waits (raise DOCSER_MAX_IN_FLIGHT if this is frequent)",
+            self.config.max_in_flight, target
+        );
+        let started = Instant::now();
+        let permit = self
+            .in_flight
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| DocserError::Browser("Operation limiter is closed".into()))?;
+        debug!("{} waited {}ms for an operation slot", target, started.elapsed().as_millis());
+        Ok(permit)
+    }
 
-            for indicator in &ready_indicators {
-                let exists_str: String = page
-                    .evaluate_value(&format!("!!({})", indicator))
+    // Every call gets its own context so concurrent requests never share cookies or a page.
+    // At most context_pool_size contexts are open at once; further callers wait for a slot.
+    async fn open_page(&self, options: &ScrapeOptions) -> Result<PageLease, DocserError> {
+        let permit = match self.context_slots.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                debug!(
+                    "All {} browser contexts are busy, waiting for a free one",
+                    self.config.context_pool_size
+                );
+                self.context_slots
+                    .clone()
+                    .acquire_owned()
                     .await
-                    .unwrap_or_else(|_| "false".to_string());
-
-                if exists_str == "true" {
-                    // Additional check: ensure the element has meaningful content
-                    let content_len_str: String = page
-                        .evaluate_value(&format!("({}).textContent.trim().length", indicator))
-                        .await
-                        .unwrap_or_else(|_| "0".to_string());
-
-                    let content_len: usize = content_len_str.parse().map_or(0, |v| v);
-
-                    if content_len > 100 {
-                        // Check stability: ensure content doesn't change over next 3 ticks
-                        let mut stable = true;
-                        let initial_len = content_len;
-                        for _ in 0..3 {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(check_interval_ms)).await;
-                            let current_len_str: String = page
-                                .evaluate_value(&format!("({}).textContent.trim().length", indicator))
-                                .await
-                                .unwrap_or_else(|_| "0".to_string());
-                            let current_len: usize = current_len_str.parse().map_or(0, |v| v);
-                            if current_len != initial_len {
-                                stable = false;
-                                break;
+                    .map_err(|_| DocserError::Browser("Browser context pool is closed".into()))?
+            }
+        };
+
+        // All pages share the persistent context, so per-request context settings (user agent,
+        // viewport, locale, storage state, HTTP credentials) don't apply there; page-level options still do
+        if let Some(user_data_dir) = &self.config.user_data_dir {
+            let context = self.get_persistent_context(user_data_dir).await?;
+            let page = match context.new_page().await {
+                Ok(page) => page,
+                Err(e) => {
+                    warn!("Failed to open a page in the persistent context ({}), relaunching it", e);
+                    self.persistent_context.lock().await.take();
+                    self.get_persistent_context(user_data_dir).await?.new_page().await?
+                }
+            };
+            return Ok(PageLease {
+                context: None,
+                page,
+                permit: Some(permit),
+            });
+        }
+
+        let browser = self.get_browser().await?;
+        let context = match browser.new_context_with_options(self.context_options(options)?).await {
+            Ok(context) => context,
+            Err(e) => {
+                warn!("Failed to open browser context ({}), recycling browser", e);
+                self.recycle_browser().await;
+                self.get_browser()
+                    .await?
+                    .new_context_with_options(self.context_options(options)?)
+                    .await?
+            }
+        };
+        let page = match context.new_page().await {
+            Ok(page) => page,
+            Err(e) => {
+                close_context(context).await;
+                return Err(e.into());
+            }
+        };
+        Ok(PageLease {
+            context: Some(context),
+            page,
+            permit: Some(permit),
+        })
+    }
+
+    // Launches Playwright and the browser if they aren't running yet and round-trips a blank
+    // page, reporting failures instead of returning them
+    pub async fn health_check(&self) -> HealthStatus {
+        let started = Instant::now();
+        let result = async {
+            // A persistent context has no separate browser handle to ask for its version
+            let version = match self.config.user_data_dir {
+                Some(_) => None,
+                None => Some(self.get_browser().await?.version()),
+            };
+            let lease = self.open_page(&ScrapeOptions::default()).await?;
+            let navigation = lease.page.goto("about:blank", None).await;
+            lease.close().await;
+            navigation?;
+            Ok::<_, DocserError>(version)
+        }
+        .await;
+
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let engine = self.config.engine.to_string();
+        match result {
+            Ok(version) => HealthStatus { operational: true, engine, version, error: None, elapsed_ms },
+            Err(e) => {
+                error!("Health check failed: {}", e);
+                HealthStatus { operational: false, engine, version: None, error: Some(e.to_string()), elapsed_ms }
+            }
+        }
+    }
+
+    async fn check_robots(&self, url: &str) -> Result<(), DocserError> {
+        let parsed = Url::parse(url)
+            .map_err(|e| DocserError::InvalidArgument(format!("Invalid URL '{}': {}", url, e)))?;
+        if !self.robots.is_allowed(&parsed).await {
+            return Err(DocserError::Disallowed(url.to_string()));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "scrape", skip_all, fields(url = %redact_credentials(url)))]
+    pub async fn scrape_page(&self, url: &str, options: &ScrapeOptions) -> Result<ScrapedPage, DocserError> {
+        let (url, options) = self.prepare_target(url, options);
+        let (url, options) = (url.as_str(), options.as_ref());
+        if options.respect_robots.unwrap_or(false) {
+            self.check_robots(url).await?;
+        }
+
+        // Options change the output, so they are part of the key alongside the normalized URL
+        let cache_key = format!("{}\n{}", self.normalize_url(url), options.cache_key()?);
+        if let Some(page) = self.page_cache.get(&cache_key) {
+            debug!("Page cache hit for {}", url);
+            self.metrics.record_page_cache_hit();
+            return Ok(self.focus_fragment(url, page));
+        }
+        debug!("Page cache miss for {}", url);
+
+        let result = self.scrape_page_uncached(url, options).await;
+        self.metrics.record_scrape(&result);
+        let page = result?;
+        self.page_cache.put(cache_key, page.clone());
+        Ok(self.focus_fragment(url, page))
+    }
+
+    // Narrows a page to the section its URL fragment points at (#installation). The cache holds
+    // the whole page, since fragments don't change what is fetched; a fragment with no matching
+    // anchor leaves the page as it is.
+    fn focus_fragment(&self, url: &str, mut page: ScrapedPage) -> ScrapedPage {
+        let Some(fragment) = Url::parse(url).ok().and_then(|parsed| parsed.fragment().map(str::to_string)) else {
+            return page;
+        };
+        let anchor = urlencoding::decode(&fragment).map(|a| a.into_owned()).unwrap_or(fragment);
+        if anchor.is_empty() {
+            return page;
+        }
+        let Some(section) = page.html.as_deref().and_then(|html| extractor::section_for_anchor(html, &anchor)) else {
+            debug!("No #{} anchor on {}, returning the whole page", anchor, url);
+            return page;
+        };
+
+        let markdown = extractor::to_markdown(&section, self.config.markdown_converter);
+        if markdown.trim().is_empty() {
+            return page;
+        }
+        debug!("Sliced {} to #{} ({} of {} chars)", url, anchor, markdown.len(), page.markdown.len());
+        page.url = url.to_string();
+        page.content_hash = extractor::content_hash(&markdown);
+        page.markdown = markdown;
+        page.html = Some(section);
+        page.extraction = Some(format!("{}#{}", page.extraction.as_deref().unwrap_or("raw"), anchor));
+        page
+    }
+
+    // Outbound links of a page (absolute, deduplicated, in document order). Goes through
+    // scrape_page, so a page that was just crawled is served from the page cache.
+    pub async fn page_links(&self, url: &str, options: &ScrapeOptions, same_domain: bool) -> Result<Vec<Link>, DocserError> {
+        let page = self.scrape_page(url, options).await?;
+        if !same_domain {
+            return Ok(page.links);
+        }
+        let host = Url::parse(&page.response.final_url)
+            .or_else(|_| Url::parse(url))
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_default();
+        Ok(page
+            .links
+            .into_iter()
+            .filter(|link| Url::parse(&link.href).is_ok_and(|href| href.host_str() == Some(host.as_str())))
+            .collect())
+    }
+
+    // Scrapes several pages concurrently (bounded by the context pool). Each URL gets its
+    // own result, in input order, so one failing page doesn't sink the batch.
+    pub async fn scrape_pages(&self, urls: &[String], options: &ScrapeOptions) -> Vec<(String, Result<ScrapedPage, DocserError>)> {
+        let mut tasks = JoinSet::new();
+        for (index, url) in urls.iter().enumerate() {
+            let manager = self.clone();
+            let url = url.clone();
+            let options = options.clone();
+            tasks.spawn(async move { (index, manager.scrape_page(&url, &options).await) });
+        }
+
+        let mut results: Vec<Option<Result<ScrapedPage, DocserError>>> = urls.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => error!("Crawl task failed: {}", e),
+            }
+        }
+
+        urls.iter()
+            .cloned()
+            .zip(results)
+            .map(|(url, result)| {
+                let result = result.unwrap_or_else(|| Err(DocserError::Browser("Crawl task aborted".into())));
+                (url, result)
+            })
+            .collect()
+    }
+
+    // Breadth-first crawl from start_url, following links on the same host that the path filter
+    // allows. Each depth level is scraped as one concurrent batch; max_pages caps the total number
+    // of pages fetched.
+    #[tracing::instrument(name = "crawl_site", skip_all, fields(url = %redact_credentials(start_url), max_depth = max_depth, max_pages = max_pages))]
+    pub async fn crawl_site(
+        &self,
+        start_url: &str,
+        max_depth: u32,
+        max_pages: usize,
+        filter: &PathFilter,
+        options: &ScrapeOptions,
+    ) -> Result<Vec<SitePage>, DocserError> {
+        let (start_url, options) = self.prepare_target(start_url, options);
+        let (start_url, options) = (start_url.as_str(), options.as_ref());
+        let start = Url::parse(start_url)
+            .map_err(|e| DocserError::InvalidArgument(format!("Invalid URL '{}': {}", start_url, e)))?;
+        let host = start
+            .host_str()
+            .ok_or_else(|| DocserError::InvalidArgument(format!("URL '{}' has no host", start_url)))?
+            .to_string();
+
+        // Recursive crawls are polite by default; callers can still opt out explicitly
+        let mut options = options.clone();
+        options.respect_robots = Some(options.respect_robots.unwrap_or(true));
+
+        let mut seen = HashSet::new();
+        seen.insert(self.normalize_url(start_url));
+        // First URL seen with each content hash; later pages with the same content are flagged
+        let mut first_with_hash: HashMap<String, String> = HashMap::new();
+        let mut frontier = vec![start_url.to_string()];
+        let mut pages = Vec::new();
+        // Position of each URL in the start page's links (its sidebar, in document order), which
+        // orders the combined document; the start page itself comes first
+        let mut nav_order: HashMap<String, usize> = HashMap::from([(self.normalize_url(start_url), 0)]);
+
+        for depth in 0..=max_depth {
+            if frontier.is_empty() || pages.len() >= max_pages {
+                break;
+            }
+            frontier.truncate(max_pages - pages.len());
+            debug!("Crawling {} pages at depth {} of {}", frontier.len(), depth, host);
+
+            let mut next = Vec::new();
+            for (url, result) in self.scrape_pages(&frontier, &options).await {
+                match result {
+                    Ok(page) => {
+                        // A duplicate has the same links as the original, so they aren't followed again
+                        if let Some(original) = first_with_hash.get(&page.content_hash) {
+                            debug!("{} has the same content as {}", url, original);
+                            pages.push(SitePage {
+                                url,
+                                depth,
+                                title: None,
+                                markdown: None,
+                                content_hash: Some(page.content_hash),
+                                duplicate_of: Some(original.clone()),
+                                error: None,
+                                nav_position: None,
+                            });
+                            continue;
+                        }
+                        first_with_hash.insert(page.content_hash.clone(), url.clone());
+                        if depth == 0 {
+                            for link in &page.links {
+                                let position = nav_order.len();
+                                nav_order.entry(self.normalize_url(&link.href)).or_insert(position);
                             }
                         }
-                        if stable {
-                            ready = true;
-                            eprintln!(
-                                "DEBUG: Page ready with stable content '{}' ({} chars) on attempt {}",
-                                indicator,
-                                initial_len,
-                                attempt + 1
-                            );
-                            break;
+                        if depth < max_depth {
+                            for link in &page.links {
+                                if is_crawlable(&link.href, &host, filter) && seen.insert(self.normalize_url(&link.href)) {
+                                    next.push(link.href.clone());
+                                }
+                            }
                         }
+                        pages.push(SitePage {
+                            url,
+                            depth,
+                            title: page.metadata.title,
+                            markdown: Some(page.markdown),
+                            content_hash: Some(page.content_hash),
+                            duplicate_of: None,
+                            error: None,
+                            nav_position: None,
+                        });
+                    }
+                    Err(e) => {
+                        warn!("Failed to crawl {}: {}", url, e);
+                        pages.push(SitePage {
+                            url,
+                            depth,
+                            title: None,
+                            markdown: None,
+                            content_hash: None,
+                            duplicate_of: None,
+                            error: Some(e.to_string()),
+                            nav_position: None,
+                        });
                     }
                 }
             }
+            frontier = next;
+        }
 
-            if ready {
-                page_ready = true;
-                // Final stabilization delay
-                tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                break;
+        for page in &mut pages {
+            page.nav_position = nav_order.get(&self.normalize_url(&page.url)).copied();
+        }
+        info!("Site crawl of {} finished with {} pages", host, pages.len());
+        Ok(pages)
+    }
+
+    // Exponential backoff after failed attempt number `attempt` (1-based): backoff_base, twice
+    // that, four times... capped at backoff_max
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(31));
+        self.config.backoff_base.saturating_mul(factor).min(self.config.backoff_max)
+    }
+
+    // Retries transient failures (see DocserError::is_retryable) with exponential backoff, each
+    // attempt in a fresh context
+    async fn scrape_page_uncached(&self, url: &str, options: &ScrapeOptions) -> Result<ScrapedPage, DocserError> {
+        let deadline = Deadline::after(self.config.operation_budget);
+        let _permit = self.acquire_in_flight(url).await?;
+        let attempts = self.config.retry_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let lease = self.open_page(options).await?;
+            let started = Instant::now();
+            let scrape = self.scrape_with_page(&lease, url, options);
+            let result = match deadline.remaining() {
+                Some(remaining) => tokio::time::timeout(remaining, scrape).await.unwrap_or_else(|_| {
+                    Err(DocserError::Timeout(format!(
+                        "operation budget of {}ms used up scraping {}",
+                        self.config.operation_budget.as_millis(),
+                        url
+                    )))
+                }),
+                None => scrape.await,
+            };
+            self.metrics.record_scrape_attempt(started.elapsed());
+            lease.close().await;
+
+            match result {
+                Err(e) if attempt < attempts && e.is_retryable() && deadline.allows(self.backoff_delay(attempt)) => {
+                    let delay = self.backoff_delay(attempt);
+                    warn!(
+                        "Scraping {} failed on attempt {} of {} ({}), retrying after {}ms",
+                        url,
+                        attempt,
+                        attempts,
+                        e,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
             }
+        }
+    }
+
+    // Crawls the pages a sitemap (or sitemap index) lists, optionally only those under url_prefix
+    // or matching url_pattern, up to max_pages, through the same cache and limits as crawl_urls.
+    // Unless forced, a page crawled before is reused when its <lastmod> hasn't advanced or, for
+    // entries without one, when the server confirms it is unchanged via its ETag/Last-Modified.
+    #[tracing::instrument(name = "crawl_sitemap", skip_all, fields(url = %sitemap_url, max_pages = max_pages))]
+    pub async fn crawl_sitemap(
+        &self,
+        sitemap_url: &str,
+        url_prefix: Option<&str>,
+        url_pattern: Option<&str>,
+        max_pages: usize,
+        force: bool,
+        options: &ScrapeOptions,
+    ) -> Result<Vec<(String, Result<ScrapedPage, DocserError>)>, DocserError> {
+        let pattern = url_pattern.map(sitemap::glob_to_regex).transpose()?;
+        let entries = sitemap::fetch_entries(&self.http, sitemap_url).await?;
+        let total = entries.len();
+        let entries: Vec<SitemapEntry> = entries
+            .into_iter()
+            .filter(|entry| url_prefix.is_none_or(|prefix| entry.loc.starts_with(prefix)))
+            .filter(|entry| pattern.as_ref().is_none_or(|pattern| pattern.is_match(&entry.loc)))
+            .take(max_pages)
+            .collect();
+
+        // Like crawl_site, a bulk crawl honours robots.txt unless the caller opts out
+        let mut options = options.clone();
+        options.respect_robots = Some(options.respect_robots.unwrap_or(true));
+        let options_key = options.cache_key()?;
+
+        let mut results: Vec<Option<Result<ScrapedPage, DocserError>>> = entries.iter().map(|_| None).collect();
+        let keys: Vec<String> = entries
+            .iter()
+            .map(|entry| format!("{}\n{}", self.normalize_url(&entry.loc), options_key))
+            .collect();
 
-            tokio::time::sleep(tokio::time::Duration::from_millis(check_interval_ms)).await;
+        // Entries with a newer <lastmod> are stale outright; those without one are checked against
+        // the server concurrently, each through the host rate limiter like any other request
+        let mut checks = JoinSet::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let previous = match (&self.recrawl_cache, force) {
+                (Some(cache), false) => cache.get(&keys[index]).unwrap_or_else(|e| {
+                    warn!("Recrawl cache read failed: {}", e);
+                    None
+                }),
+                _ => None,
+            };
+            let Some(previous) = previous else {
+                continue;
+            };
+            match (entry.lastmod, previous.lastmod) {
+                (Some(lastmod), Some(seen)) if lastmod <= seen => {
+                    results[index] = Some(Ok(previous.into_page(&entry.loc)));
+                }
+                (None, _) => {
+                    let manager = self.clone();
+                    let url = entry.loc.clone();
+                    checks.spawn(async move {
+                        let unchanged = manager.unchanged_since(&url, &previous).await;
+                        (index, unchanged.then(|| previous.into_page(&url)))
+                    });
+                }
+                _ => {}
+            }
+        }
+        while let Some(joined) = checks.join_next().await {
+            match joined {
+                Ok((index, Some(page))) => results[index] = Some(Ok(page)),
+                Ok((_, None)) => {}
+                Err(e) => error!("Freshness check task failed: {}", e),
+            }
+        }
+
+        let stale: Vec<usize> = (0..entries.len()).filter(|index| results[*index].is_none()).collect();
+        info!(
+            "Crawling {} of {} pages listed in {} ({} unchanged since the last crawl)",
+            stale.len(),
+            total,
+            sitemap_url,
+            entries.len() - stale.len()
+        );
+
+        let urls: Vec<String> = stale.iter().map(|index| entries[*index].loc.clone()).collect();
+        let crawled = self.scrape_pages(&urls, &options).await;
+        for (index, (_, result)) in stale.into_iter().zip(crawled) {
+            if let (Some(cache), Ok(page)) = (&self.recrawl_cache, &result)
+                && let Err(e) = cache.put(&keys[index], &RecrawlEntry::new(page, entries[index].lastmod))
+            {
+                warn!("Recrawl cache write failed: {}", e);
+            }
+            results[index] = Some(result);
         }
 
-        if !page_ready {
-            eprintln!("WARNING: Page did not become ready within timeout");
+        Ok(entries
+            .into_iter()
+            .zip(results)
+            .map(|(entry, result)| {
+                let result = result.unwrap_or_else(|| Err(DocserError::Browser("Crawl task aborted".into())));
+                (entry.loc, result)
+            })
+            .collect())
+    }
+
+    // Asks the server whether a page changed since it was crawled, using the ETag or Last-Modified
+    // of that response. Anything but a clear "not modified" counts as changed.
+    async fn unchanged_since(&self, url: &str, previous: &RecrawlEntry) -> bool {
+        let etag = previous.etag.as_ref();
+        let last_modified = previous.last_modified.as_ref();
+        if etag.is_none() && last_modified.is_none() {
+            return false;
         }
 
-        // Get the HTML content, expanding shadow roots and handling slots, excluding style and script tags
-        let html: String = page.evaluate_value(load_js_script()).await?;
-
-        // Extract main content using readability
-        let cleaned_html = if let Ok(mut parser) = Readability::new(&html, Some(ReadabilityOptions {
-            char_threshold: 500,
-            debug: false,
-            ..Default::default()
-        })) {
-            if let Some(article) = parser.parse() {
-                if let Some(content) = article.content {
-                    eprintln!("DEBUG: Readability extracted content ({} chars)", content.len());
-                    content
+        self.rate_limiter.wait(url).await;
+        let mut request = self.http.head(url).timeout(Duration::from_secs(10));
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        match request.send().await {
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_MODIFIED => true,
+            // Servers that ignore conditional requests still echo the current ETag
+            Ok(response) => etag.is_some_and(|etag| {
+                response.headers().get("etag").and_then(|value| value.to_str().ok()) == Some(etag.as_str())
+            }),
+            Err(e) => {
+                debug!("Freshness check for {} failed: {}", url, e);
+                false
+            }
+        }
+    }
+
+    // Prints the page to PDF once it has rendered. Only Chromium implements page.pdf().
+    #[tracing::instrument(name = "render_pdf", skip_all, fields(url = %redact_credentials(url)))]
+    pub async fn render_pdf(&self, url: &str, format: PaperFormat, print_background: bool, options: &ScrapeOptions) -> Result<Vec<u8>, DocserError> {
+        let (url, options) = self.prepare_target(url, options);
+        let (url, options) = (url.as_str(), options.as_ref());
+        if self.config.engine != BrowserEngine::Chromium {
+            return Err(DocserError::InvalidArgument(format!(
+                "PDF rendering requires the chromium engine (set DOCSER_BROWSER=chromium), current engine is {}",
+                self.config.engine
+            )));
+        }
+        if options.respect_robots.unwrap_or(false) {
+            self.check_robots(url).await?;
+        }
+
+        let _permit = self.acquire_in_flight(url).await?;
+        let lease = self.open_page(options).await?;
+        let result = async {
+            self.navigate(&lease, url, options).await?;
+
+            let pdf_options = PdfOptions::builder()
+                .format(format.as_str().to_string())
+                .print_background(print_background)
+                .build();
+            let pdf = lease.page.pdf(Some(pdf_options)).await?;
+            debug!("Rendered {} to PDF ({} bytes)", url, pdf.len());
+            Ok(pdf)
+        }
+        .await;
+        lease.close().await;
+        result
+    }
+
+    // Runs content extraction over HTML supplied by the caller, with the configured converter
+    pub fn extract_html(
+        &self,
+        html: &str,
+        page_url: Option<&str>,
+        exclusions: &[Selector],
+        strategy: &[ExtractionStrategy],
+    ) -> (String, ExtractionTier) {
+        extractor::extract_markdown(html, page_url, exclusions, strategy, self.config.markdown_converter)
+    }
+
+    // The page's rendered HTML (shadow roots expanded, scripts and styles dropped) before any
+    // content extraction, for tools that inspect the page rather than convert it
+    #[tracing::instrument(name = "fetch_html", skip_all, fields(url = %redact_credentials(url)))]
+    pub async fn fetch_html(&self, url: &str, options: &ScrapeOptions) -> Result<String, DocserError> {
+        let (url, options) = self.prepare_target(url, options);
+        let (url, options) = (url.as_str(), options.as_ref());
+        if options.respect_robots.unwrap_or(false) {
+            self.check_robots(url).await?;
+        }
+
+        let _permit = self.acquire_in_flight(url).await?;
+        let lease = self.open_page(options).await?;
+        let result = async {
+            self.navigate(&lease, url, options).await?;
+            if options.expand_collapsed {
+                expand_collapsed(&lease.page).await;
+            }
+            if let Some(steps) = options.scroll_steps {
+                let delay = Duration::from_millis(options.scroll_delay_ms.unwrap_or(DEFAULT_SCROLL_DELAY_MS));
+                auto_scroll(&lease.page, steps, delay).await;
+            }
+
+            composed_html(&lease.page, url, options).await
+        }
+        .await;
+        lease.close().await;
+        result
+    }
+
+    fn goto_options(&self, options: &ScrapeOptions) -> GotoOptions {
+        let navigation_timeout = options
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(self.config.navigation_timeout);
+        GotoOptions::new()
+            .wait_until(wait_until(options.wait_until))
+            .timeout(navigation_timeout)
+    }
+
+    // Waits for client-rendered content to settle. Never fails: if the page doesn't look ready
+    // within the readiness timeout, whatever has rendered so far is used.
+    async fn wait_for_ready(&self, page: &Page, options: &ScrapeOptions) {
+        let max_wait_ms = options
+            .ready_timeout_ms
+            .unwrap_or(self.config.ready_timeout.as_millis() as u64);
+
+        // An explicit wait_for selector replaces the content heuristics: the page counts as
+        // ready once the element exists, whether or not it holds any text
+        if let Some(selector) = &options.wait_for {
+            let indicator = [format!("document.querySelector({})", js_string(selector))];
+            if !wait_for_any_indicator(page, &indicator, max_wait_ms, READY_CHECK_INTERVAL_MS, None).await {
+                warn!("'{}' did not appear within {}ms, extracting anyway", selector, max_wait_ms);
+            }
+            return;
+        }
+
+        // Smart waiting for SPA content: poll the readiness selectors (caller-supplied, or the
+        // defaults covering content areas and common framework roots) until one holds text
+        let ready_selectors: Vec<&str> = match &options.ready_selectors {
+            Some(selectors) if !selectors.is_empty() => selectors.iter().map(String::as_str).collect(),
+            _ => DEFAULT_READY_SELECTORS.to_vec(),
+        };
+        let ready_indicators: Vec<String> = ready_selectors
+            .iter()
+            .map(|selector| format!("document.querySelector({})", js_string(selector)))
+            .collect();
+
+        let content_check = ContentCheck {
+            min_len: options.ready_min_text_length.unwrap_or(self.config.ready_min_text_length),
+            stable_polls: options.ready_stable_polls.unwrap_or(self.config.ready_stable_polls),
+        };
+        if !wait_for_any_indicator(page, &ready_indicators, max_wait_ms, READY_CHECK_INTERVAL_MS, Some(content_check)).await {
+            warn!("Page did not become ready within timeout");
+        }
+    }
+
+    // Aborts requests the caller doesn't need for text extraction. Must run before navigation.
+    async fn install_request_blocking(&self, page: &Page, options: &ScrapeOptions) -> Result<(), DocserError> {
+        let patterns: Vec<String> = options.block_url_patterns.clone().unwrap_or_default();
+        if !options.block_resources && patterns.is_empty() {
+            return Ok(());
+        }
+        let block_resources = options.block_resources;
+        page.route("**/*", move |route| {
+            let patterns = patterns.clone();
+            async move {
+                let request = route.request();
+                let blocked = (block_resources && BLOCKED_RESOURCE_TYPES.contains(&request.resource_type()))
+                    || patterns.iter().any(|pattern| request.url().contains(pattern.as_str()));
+                if blocked {
+                    route.abort(None).await
                 } else {
-                    eprintln!("WARNING: Readability found no content, falling back to extractor module");
-                    extractor::extract_content(&html)
+                    route.continue_(None).await
                 }
-            } else {
-                eprintln!("WARNING: Readability parsing failed, falling back to extractor module");
-                extractor::extract_content(&html)
             }
+        })
+        .await?;
+        Ok(())
+    }
+
+    // Sets the request's cookies on the lease's context, scoped to the target URL so they are
+    // never sent to other hosts. Must run before navigation.
+    async fn install_cookies(&self, lease: &PageLease, url: &str, options: &ScrapeOptions) -> Result<(), DocserError> {
+        let Some(cookies) = options.cookies.as_ref().filter(|cookies| !cookies.is_empty()) else {
+            return Ok(());
+        };
+        // Cookies added to the shared persistent context would stick to every later request
+        let Some(context) = &lease.context else {
+            warn!("cookies are ignored with a persistent browser profile (DOCSER_USER_DATA_DIR)");
+            return Ok(());
+        };
+        let cookies: Vec<Cookie> = cookies
+            .iter()
+            .map(|(name, value)| Cookie {
+                name: name.clone(),
+                value: value.clone(),
+                url: Some(url.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        // Only the count is logged; cookie values are credentials
+        debug!("Setting {} cookies", cookies.len());
+        context.add_cookies(&cookies).await?;
+        Ok(())
+    }
+
+    // Runs the caller's init_script in every document of the page before the site's own scripts.
+    // Must run before navigation.
+    async fn install_init_script(&self, page: &Page, options: &ScrapeOptions) -> Result<(), DocserError> {
+        if let Some(script) = &options.init_script {
+            debug!("Installing init script ({} chars)", script.len());
+            page.add_init_script(script).await?;
+        }
+        Ok(())
+    }
+
+    // Opens url in the lease's page the same way for every tool: request blocking, cookies and
+    // the init script installed first, the host rate limit, an error for non-2xx statuses, the
+    // single canonical hop when follow_canonical is set, then the readiness wait for pages that
+    // go through extraction (text, JSON and binary responses have nothing to wait for)
+    async fn navigate(&self, lease: &PageLease, url: &str, options: &ScrapeOptions) -> Result<Navigation, DocserError> {
+        let page = &lease.page;
+        self.install_request_blocking(page, options).await?;
+        self.install_cookies(lease, url, options).await?;
+        self.install_init_script(page, options).await?;
+
+        let mut response = self.goto(page, url, options).await?;
+
+        // Re-navigate to the declared canonical URL. This is a single hop: the canonical
+        // page's own canonical link is not followed, so misconfigured sites can't loop us. It
+        // stays on the same site, so the request's credentials and cookies never go to a host the
+        // caller didn't name, and robots.txt is checked for it like for the original URL.
+        let mut canonical_url = None;
+        if options.follow_canonical {
+            let canonical: String = page
+                .evaluate_value("document.querySelector('link[rel=\"canonical\"]')?.href || ''")
+                .await
+                .unwrap_or_default();
+            let current: String = page.evaluate_value("location.href").await.unwrap_or_default();
+
+            if !canonical.is_empty() && !same_site(&canonical, &current) {
+                debug!("Not following canonical URL {} on another site (loaded {})", canonical, current);
+            } else if !canonical.is_empty() && self.normalize_url(&canonical) != self.normalize_url(&current) {
+                if options.respect_robots.unwrap_or(false) {
+                    self.check_robots(&canonical).await?;
+                }
+                debug!("Following canonical URL {} (loaded {})", canonical, current);
+                response = self.goto(page, &canonical, options).await?;
+                canonical_url = Some(canonical);
+            }
+        }
+
+        if matches!(self.classify_content_type(response.content_type.as_deref()), ContentKind::Extractable) {
+            self.wait_for_ready(page, options).await;
+        }
+        Ok(Navigation { response, canonical_url })
+    }
+
+    // One page load through the host rate limiter; statuses outside 2xx are errors
+    async fn goto(&self, page: &Page, url: &str, options: &ScrapeOptions) -> Result<ResponseInfo, DocserError> {
+        self.rate_limiter.wait(url).await;
+        let response = page
+            .goto(url, Some(self.goto_options(options)))
+            .await
+            .map_err(DocserError::navigation)?
+            .ok_or_else(|| DocserError::Navigation(format!("No response for {}", url)))?;
+        if !response.ok() {
+            return Err(DocserError::HttpStatus(response.status()));
+        }
+        let headers: BTreeMap<String, String> = response.headers().clone().into_iter().collect();
+        Ok(ResponseInfo {
+            status: response.status(),
+            final_url: response.url().to_string(),
+            content_type: headers.get("content-type").cloned(),
+            headers,
+        })
+    }
+
+    async fn scrape_with_page(&self, lease: &PageLease, url: &str, options: &ScrapeOptions) -> Result<ScrapedPage, DocserError> {
+        let page = &lease.page;
+        let Navigation { response: response_info, canonical_url } = self.navigate(lease, url, options).await?;
+        if self.normalize_url(&response_info.final_url) != self.normalize_url(canonical_url.as_deref().unwrap_or(url)) {
+            debug!("{} was redirected to {}", url, response_info.final_url);
+        }
+
+        match self.classify_content_type(response_info.content_type.as_deref()) {
+            ContentKind::Extractable => {}
+            kind @ (ContentKind::Text | ContentKind::Json) => {
+                // Browsers render text responses inside a <pre>, so innerText gives the raw body back
+                let mut text: String = page
+                    .evaluate_value("document.body ? document.body.innerText : document.documentElement.textContent")
+                    .await?;
+                if matches!(kind, ContentKind::Json) {
+                    // Pretty-printed in a fenced block; bodies that don't parse are returned verbatim
+                    if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+                        text = format!("```json\n{}\n```", serde_json::to_string_pretty(&value)?);
+                    }
+                }
+                debug!("Returning non-HTML response as text ({} chars)", text.len());
+                return Ok(ScrapedPage {
+                    url: url.to_string(),
+                    canonical_url,
+                    content_hash: extractor::content_hash(&text),
+                    markdown: text,
+                    html: None,
+                    toc: None,
+                    blocks: None,
+                    metadata: PageMetadata::default(),
+                    response: response_info,
+                    extraction: None,
+                    reader: None,
+                    links: Vec::new(),
+                });
+            }
+            ContentKind::Binary(content_type) => {
+                return Err(DocserError::UnsupportedContentType(format!(
+                    "{} (binary content can't be converted to markdown)",
+                    content_type
+                )));
+            }
+            ContentKind::Unsupported(content_type) => {
+                return Err(DocserError::UnsupportedContentType(content_type));
+            }
+        }
+
+        if options.expand_collapsed {
+            expand_collapsed(page).await;
+        }
+        if let Some(steps) = options.scroll_steps {
+            let delay = Duration::from_millis(options.scroll_delay_ms.unwrap_or(DEFAULT_SCROLL_DELAY_MS));
+            auto_scroll(page, steps, delay).await;
+        }
+
+        // Get the HTML content, expanding shadow roots and handling slots, excluding style and script tags
+        let html = composed_html(page, url, options).await?;
+
+        // Relative URLs resolve against where the page actually ended up after redirects (the
+        // canonical page when it was followed), not the URL that was asked for
+        let page_url = if response_info.final_url.is_empty() {
+            canonical_url.as_deref().unwrap_or(url)
+        } else {
+            response_info.final_url.as_str()
+        };
+        // Outbound links come from the whole page, navigation included, so crawls can follow them
+        let links = extractor::extract_links(&html, page_url);
+        let base_url = extractor::base_url(&html, page_url);
+        let mut metadata = extractor::extract_metadata(&html, page_url);
+
+        let subtree = match &options.selector {
+            Some(selector) => {
+                let subtree = extractor::select_subtree(&html, selector).map_err(DocserError::InvalidArgument)?;
+                if subtree.is_none() {
+                    warn!("Selector '{}' matched nothing on {}, extracting the whole page", selector, page_url);
+                }
+                subtree.map(|subtree| (subtree, selector))
+            }
+            None => None,
+        };
+
+        // Strip framework chrome (sidebars, nav, TOCs) unless the caller asked for the raw page
+        let (cleaned_html, extraction, reader) = if let Some((subtree, selector)) = subtree {
+            debug!("Extracted {} chars matching '{}'", subtree.len(), selector);
+            (subtree, Some(format!("selector:{}", selector)), None)
+        } else if options.raw {
+            debug!("Raw mode, skipping content extraction");
+            (html, None, None)
         } else {
-            eprintln!("WARNING: Failed to initialize Readability, falling back to extractor module");
-            extractor::extract_content(&html)
+            let exclusions = extractor::exclusion_selectors(
+                options.extra_exclusions.as_deref().unwrap_or_default(),
+                !options.disable_default_exclusions,
+            );
+            let strategy = options.strategy.as_deref().unwrap_or(ExtractionStrategy::DEFAULT_ORDER);
+            let (content, tier) = extractor::extract_content(&html, &exclusions, strategy);
+            debug!("Extracted content via {} ({} of {} chars)", tier, content.len(), html.len());
+            (content, Some(tier.to_string()), tier.into_reader())
         };
 
-        // Convert to markdown
-        let markdown = html2md::parse_html(&cleaned_html);
+        // Convert to markdown, with links and images made absolute so the output is self-contained
+        let html = extractor::absolutize_urls(&cleaned_html, &base_url);
+        let markdown = extractor::to_markdown(&html, self.config.markdown_converter);
+        // An empty result is a failed render far more often than a blank page, so it is reported
+        // (and retried) rather than returned as an empty success
+        if markdown.trim().is_empty() {
+            return Err(DocserError::NoContent(format!(
+                "{} produced no text (extraction: {})",
+                page_url,
+                extraction.as_deref().unwrap_or("raw")
+            )));
+        }
+        if metadata.language.is_none() {
+            metadata.language = extractor::detect_language(&extractor::to_text(&html));
+        }
 
-        eprintln!("DEBUG: Markdown length: {}", markdown.len());
-        Ok(markdown)
+        debug!("Markdown length: {}", markdown.len());
+        Ok(ScrapedPage {
+            url: url.to_string(),
+            canonical_url,
+            content_hash: extractor::content_hash(&markdown),
+            markdown,
+            html: Some(html),
+            toc: None,
+            blocks: None,
+            metadata,
+            response: response_info,
+            extraction,
+            reader,
+            links,
+        })
     }
 
-    pub async fn search_android_dev(&self, query: &str, max_page: u32) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let url = format!(
-            "https://developer.android.com/s/results?q={}",
-            urlencoding::encode(query)
-        );
-        let playwright = self.get_playwright().await?;
+    pub async fn search_android_dev(&self, query: &str, max_page: u32, refresh: bool, allow_empty: bool, lang: Option<&str>) -> Result<String, DocserError> {
+        self.search_site(SearchSite::android(), query, max_page, refresh, allow_empty, lang).await
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.metrics.snapshot()
+    }
 
-        let browser = playwright.webkit().launch().await?;
-        let page = browser.new_page().await?;
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            frameworks: extractor::framework_names(),
+            search_sites: SEARCH_SITES
+                .iter()
+                .map(|site| SearchSiteInfo { name: site.name, description: site.description })
+                .collect(),
+            output_formats: OutputFormat::ALL.to_vec(),
+            engines: BrowserEngine::ALL.iter().map(ToString::to_string).collect(),
+            engine: self.config.engine.to_string(),
+            markdown_converters: MarkdownConverter::ALL.iter().map(ToString::to_string).collect(),
+            devices: Device::names(),
+        }
+    }
 
+    #[tracing::instrument(name = "search", skip_all, fields(site = site.name, query = %query, max_page = max_page))]
+    // With allow_empty, a results page that loads but lists nothing is an empty result with a note
+    // instead of a NoContent error, so "no matches" can be told apart from a broken scraper
+    pub async fn search_site(
+        &self,
+        site: &SearchSite,
+        query: &str,
+        max_page: u32,
+        refresh: bool,
+        allow_empty: bool,
+        lang: Option<&str>,
+    ) -> Result<String, DocserError> {
+        // Results differ per language, so each language is cached under its own site key
+        let cache_site = match lang {
+            Some(lang) => format!("{}:{}", site.name, lang),
+            None => site.name.to_string(),
+        };
+        // Each extra page is another round of clicking through the site's pagination
+        if max_page == 0 || max_page > self.config.max_search_pages {
+            return Err(DocserError::InvalidArgument(format!(
+                "max_page must be between 1 and {}, got {}",
+                self.config.max_search_pages, max_page
+            )));
+        }
+        if let Some(cache) = &self.search_cache {
+            if refresh {
+                debug!("Bypassing search cache for {} '{}'", site.name, query);
+            } else {
+                match cache.get(&cache_site, query, max_page) {
+                    Ok(Some(cached)) => {
+                        debug!("Search cache hit for {} '{}' (max_page {})", site.name, query, max_page);
+                        self.metrics.record_search_cache_hit();
+                        return Ok(cached);
+                    }
+                    Ok(None) => debug!("Search cache miss for {} '{}' (max_page {})", site.name, query, max_page),
+                    Err(e) => warn!("Search cache lookup failed: {}", e),
+                }
+            }
+        }
+
+        let url = site.results_url_for(query);
+        let url = lang.and_then(|lang| i18n::localize_url(&url, lang)).unwrap_or(url);
+        let page_options = ScrapeOptions { lang: lang.map(str::to_string), ..ScrapeOptions::default() };
+
+        let deadline = Deadline::after(self.config.operation_budget);
+        let _permit = self.acquire_in_flight(&url).await?;
+        let lease = self.open_page(&page_options).await?;
+        let collected = self.collect_search_links(&lease.page, site, &url, max_page, deadline).await;
+        lease.close().await;
+        self.metrics.record_search(&collected);
+
+        let (links, complete) = match collected {
+            Err(DocserError::NoContent(_)) if allow_empty => (Vec::new(), true),
+            collected => collected?,
+        };
+        // Partial results are returned but not cached, so the next call can fetch the rest
+        if !complete {
+            let result = SearchResult {
+                links,
+                note: Some(format!(
+                    "Stopped early: the operation budget of {}ms ran out before all {} result pages were read",
+                    self.config.operation_budget.as_millis(),
+                    max_page
+                )),
+            };
+            return Ok(serde_json::to_string(&result)?);
+        }
+        if links.is_empty() {
+            if !allow_empty {
+                return Err(DocserError::NoContent("No links extracted".into()));
+            }
+            // Not cached, so a transient empty page doesn't stick
+            let result = SearchResult {
+                links,
+                note: Some(format!("The {} results page loaded but listed no matches for '{}'", site.name, query)),
+            };
+            return Ok(serde_json::to_string(&result)?);
+        }
+        let result = SearchResult { links, note: None };
+        let serialized = serde_json::to_string(&result)?;
+
+        if let Some(cache) = &self.search_cache {
+            if let Err(e) = cache.put(&cache_site, query, max_page, &serialized) {
+                warn!("Failed to store search result in cache: {}", e);
+            }
+        }
+        Ok(serialized)
+    }
+
+    // The links and whether every requested page was read; a search that runs out of budget
+    // returns the links found so far as incomplete, or a timeout if it found none
+    async fn collect_search_links(
+        &self,
+        page: &Page,
+        site: &SearchSite,
+        url: &str,
+        max_page: u32,
+        deadline: Deadline,
+    ) -> Result<(Vec<Link>, bool), DocserError> {
+        let out_of_budget = || {
+            DocserError::Timeout(format!(
+                "operation budget of {}ms used up searching {}",
+                self.config.operation_budget.as_millis(),
+                site.name
+            ))
+        };
         let mut links = Vec::new();
+        let links_js = site.links_js(site.link_selector);
+        let last_page = if site.google_cse_pagination {
+            max_page
+        } else {
+            if max_page > 1 {
+                debug!("{} search has no pagination, only the first page is read", site.name);
+            }
+            1
+        };
 
-        // Retry up to 3 times
-        for attempt in 1..=3 {
+        let attempts = self.config.retry_attempts.max(1);
+        for attempt in 1..=attempts {
+            if deadline.expired() {
+                return Err(out_of_budget());
+            }
+            self.rate_limiter.wait(url).await;
             let response = page
                 .goto(
-                    &url,
+                    url,
                     Some(
                         GotoOptions::new()
                             .wait_until(WaitUntil::DomContentLoaded)
-                            .timeout(std::time::Duration::from_secs(30)),
+                            .timeout(self.config.navigation_timeout),
                     ),
                 )
-                .await?;
+                .await
+                .map_err(DocserError::navigation)?;
             if let Some(resp) = response {
                 if !resp.ok() {
-                    if attempt == 3 {
-                        return Err(format!("HTTP error: {}", resp.status()).into());
+                    let delay = self.backoff_delay(attempt);
+                    if attempt == attempts || !deadline.allows(delay) {
+                        return Err(DocserError::HttpStatus(resp.status()));
                     }
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    tokio::time::sleep(delay).await;
                     continue;
                 }
             }
 
             // Wait for search results
-            let ready_indicators = vec!["document.querySelector('.gs-title')"];
-
-            let max_wait_ms = 10000;
-            let check_interval_ms = 250;
-
-            let mut ready = false;
-            for _ in 0..(max_wait_ms / check_interval_ms) {
-                for indicator in &ready_indicators {
-                    let result: String = page
-                        .evaluate_value(&format!("!!({})", indicator))
-                        .await
-                        .unwrap_or_else(|_| "false".to_string());
-
-                    if result == "true" {
-                        ready = true;
-                        break;
-                    }
-                }
-                if ready {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                    break;
-                }
-                tokio::time::sleep(tokio::time::Duration::from_millis(check_interval_ms)).await;
-            }
-
+            let ready = wait_for_any_indicator(page, &[site.ready_js()], deadline.cap_ms(10000), READY_CHECK_INTERVAL_MS, None).await;
             if !ready {
-                eprintln!(
-                    "WARNING: Search results did not load on attempt {} of 3",
-                    attempt
+                warn!(
+                    "Search results did not load on attempt {} of {}",
+                    attempt,
+                    attempts
                 );
-                if attempt == 3 {
-                    return Err("Search results did not load after 3 attempts".into());
+                if attempt == attempts {
+                    return Err(DocserError::Timeout(format!("Search results did not load after {} attempts", attempts)));
                 }
-                // Exponential backoff: 1s, 2s, 4s
-                let backoff_secs = 2u64.pow(attempt - 1);
-                eprintln!(
-                    "INFO: Retrying after {} seconds (exponential backoff)",
-                    backoff_secs
+                let delay = self.backoff_delay(attempt);
+                if !deadline.allows(delay) {
+                    return Err(out_of_budget());
+                }
+                info!(
+                    "Retrying after {}ms (exponential backoff)",
+                    delay.as_millis()
                 );
-                tokio::time::sleep(std::time::Duration::from_secs(backoff_secs)).await;
+                tokio::time::sleep(delay).await;
             }
 
             // Extract links with more specific selector
             let extracted_links_str: String = page
-                .evaluate_value(r#"JSON.stringify(Array.from(document.querySelectorAll('.gsc-webResult.gsc-result .gs-webResult .gs-title a')).map(a => ({href: a.href, text: a.textContent.trim()})))"#)
+                .evaluate_value(&links_js)
                 .await
                 .unwrap_or_else(|_| "[]".to_string());
 
@@ -261,7 +1618,7 @@ impl BrowserManager {
             links = all_links
                 .into_iter()
                 .filter(|l| {
-                    l.href.starts_with("https://developer.android.com/")
+                    l.href.starts_with(site.href_prefix)
                         && !l.text.is_empty()
                         && seen.insert(l.href.clone())
                 })
@@ -269,168 +1626,427 @@ impl BrowserManager {
 
             // Debug: Print first few extracted links to verify
             if !links.is_empty() {
-                eprintln!("DEBUG: Found {} links in total", links.len());
+                debug!("Found {} links in total", links.len());
                 for (i, link) in links.iter().take(3).enumerate() {
-                    eprintln!("DEBUG[{}]: {}", i + 1, link.text);
+                    debug!("[{}] {}", i + 1, link.text);
                 }
             } else {
-                eprintln!("DEBUG: No links found with primary selector");
+                debug!("No links found with primary selector");
             }
 
-            if links.is_empty() {
-                eprintln!("WARNING: Primary selector found no links, trying fallback selector");
-                // Fallback
+            if links.is_empty() && let Some(fallback_selector) = site.fallback_link_selector {
+                warn!("Primary selector found no links, trying fallback selector");
                 let fallback_links_str: String = page
-                    .evaluate_value(r#"JSON.stringify(Array.from(document.querySelectorAll('.devsite-article a')).filter(a => a.href.startsWith('https://developer.android.com/') && a.textContent.trim()).reduce((acc, a) => { if (!acc.some(item => item.href === a.href)) acc.push({href: a.href, text: a.textContent.trim()}); return acc; }, []))"#)
+                    .evaluate_value(&site.links_js(fallback_selector))
                     .await
                     .unwrap_or_else(|_| "[]".to_string());
-                links = serde_json::from_str(&fallback_links_str).unwrap_or_default();
+                let fallback_links: Vec<Link> = serde_json::from_str(&fallback_links_str).unwrap_or_default();
+                links = fallback_links
+                    .into_iter()
+                    .filter(|l| {
+                        l.href.starts_with(site.href_prefix)
+                            && !l.text.is_empty()
+                            && seen.insert(l.href.clone())
+                    })
+                    .collect();
 
                 if !links.is_empty() {
-                    eprintln!("INFO: Fallback selector found {} links", links.len());
+                    info!("Fallback selector found {} links", links.len());
                 } else {
-                    eprintln!("ERROR: Both primary and fallback selectors found no links");
+                    error!("Both primary and fallback selectors found no links");
                 }
             }
 
-            // If max_page > 1, click next for additional pages
-            for page_num in 2..=max_page {
-                // Get current page number to verify navigation worked
-                let current_page: String = page
-                    .evaluate_value(
-                        "document.querySelector('.gsc-cursor-current-page')?.textContent",
-                    )
-                    .await
-                    .unwrap_or_else(|_| "-1".to_string());
-
-                eprintln!(
-                    "DEBUG: Currently on page {}, trying to navigate to page {}",
-                    current_page, page_num
-                );
+            // If max_page > 1, click through Google CSE pagination for additional pages
+            for page_num in 2..=last_page {
+                if deadline.expired() {
+                    warn!("Operation budget used up after {} of {} result pages", page_num - 1, last_page);
+                    return Ok((links, false));
+                }
+                if !goto_google_cse_page(page, page_num).await {
+                    warn!("Pagination page did not load properly within timeout");
+                    break;
+                }
 
-                // Click the target page number
-                let locator = page
-                    .locator(&format!(".gsc-cursor-page:nth-child({})", page_num))
-                    .await;
-                if locator.click(Default::default()).await.is_ok() {
-                    // Wait for results to update with specific wait conditions
-                    let max_pagination_wait_ms = 10000;
-                    let pagination_check_interval_ms = 250;
-
-                    let mut page_loaded = false;
-                    let mut loading_detected = true;
-
-                    // First wait for loading to start (might already be loading)
-                    for _ in 0..(2000 / pagination_check_interval_ms) {
-                        let result: String = page
-                            .evaluate_value("!!document.querySelector('.gsc-control-wrapper-cse.gsc-loading-fade')")
-                            .await
-                            .unwrap_or_else(|_| "false".to_string());
-
-                        if result == "true" {
-                            loading_detected = true;
-                            break;
-                        }
-                        tokio::time::sleep(tokio::time::Duration::from_millis(
-                            pagination_check_interval_ms,
-                        ))
-                        .await;
-                    }
+                // Extract more links with the same specific selector
+                let more_links_str: String = page
+                    .evaluate_value(&links_js)
+                    .await
+                    .unwrap_or_else(|_| "[]".to_string());
 
-                    // If we detected loading, wait for it to complete
-                    if loading_detected {
-                        for _ in 0..(max_pagination_wait_ms / pagination_check_interval_ms) {
-                            let result: String = page
-                                .evaluate_value("!!document.querySelector('.gsc-control-wrapper-cse.gsc-loading-fade')")
-                                .await
-                                .unwrap_or_else(|_| "false".to_string());
-
-                            if result == "false" {
-                                // Loading has completed, verify we actually reached the target page
-                                let new_page: String = page
-                                    .evaluate_value(&format!("document.querySelector('.gsc-cursor-page:nth-child({})')?.textContent", page_num))
-                                    .await
-                                    .unwrap_or_else(|_| "??".to_string());
-
-                                if new_page == page_num.to_string() {
-                                    // Successfully navigated to the target page
-                                    page_loaded = true;
-                                    eprintln!("DEBUG: Successfully navigated to page {}", page_num);
-                                    // Additional stabilization delay
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(500))
-                                        .await;
-                                    break;
-                                } else {
-                                    eprintln!(
-                                        "WARNING: Expected page {} but ended up on page {}",
-                                        page_num, new_page
-                                    );
-                                }
-                            }
-                            tokio::time::sleep(tokio::time::Duration::from_millis(
-                                pagination_check_interval_ms,
-                            ))
-                            .await;
-                        }
-                    }
+                let more_links: Vec<Link> = serde_json::from_str(&more_links_str).unwrap_or_default();
 
-                    if !page_loaded {
-                        eprintln!("WARNING: Pagination page did not load properly within timeout");
-                        break;
-                    }
+                // Filter and dedup against global seen
+                let filtered_more = more_links
+                    .into_iter()
+                    .filter(|l| {
+                        l.href.starts_with(site.href_prefix)
+                            && !l.text.is_empty()
+                            && seen.insert(l.href.clone())
+                    })
+                    .collect::<Vec<_>>();
 
-                    // Extract more links with the same specific selector
-                    let more_links_str: String = page
-                        .evaluate_value(r#"JSON.stringify(Array.from(document.querySelectorAll('.gsc-webResult.gsc-result .gs-webResult .gs-title a')).map(a => ({href: a.href, text: a.textContent.trim()})))"#)
-                        .await
-                        .unwrap_or_else(|_| "[]".to_string());
-
-                    let more_links: Vec<Link> = serde_json::from_str(&more_links_str).unwrap_or_default();
-
-                    // Filter and dedup against global seen
-                    let filtered_more = more_links
-                        .into_iter()
-                        .filter(|l| {
-                            l.href.starts_with("https://developer.android.com/")
-                                && !l.text.is_empty()
-                                && seen.insert(l.href.clone())
-                        })
-                        .collect::<Vec<_>>();
-
-                    links.extend(filtered_more);
-                }
+                links.extend(filtered_more);
             }
 
             // No next_page
 
             // If we got links, success
             if !links.is_empty() {
-                eprintln!(
-                    "INFO: Successfully extracted {} links on attempt {}",
+                info!(
+                    "Successfully extracted {} links on attempt {}",
                     links.len(),
                     attempt
                 );
                 break;
             }
 
-            if attempt == 3 {
-                return Err("No links extracted after 3 attempts".into());
+            if attempt == attempts {
+                return Err(DocserError::NoContent(format!("No links extracted after {} attempts", attempts)));
+            }
+            let delay = self.backoff_delay(attempt);
+            if !deadline.allows(delay) {
+                return Err(out_of_budget());
             }
-            // Exponential backoff: 1s, 2s, 4s
-            let backoff_secs = 2u64.pow(attempt - 1);
-            eprintln!(
-                "WARNING: No links extracted on attempt {} of 3, retrying after {} seconds",
-                attempt, backoff_secs
+            warn!(
+                "No links extracted on attempt {} of {}, retrying after {}ms",
+                attempt,
+                attempts,
+                delay.as_millis()
             );
-            tokio::time::sleep(std::time::Duration::from_secs(backoff_secs)).await;
+            tokio::time::sleep(delay).await;
+        }
+
+        Ok((links, true))
+    }
+}
+
+// The rendered page serialized by load_js_script, with a warning when the depth or node ceiling
+// left part of it out
+async fn composed_html(page: &Page, url: &str, options: &ScrapeOptions) -> Result<String, DocserError> {
+    let mut html: String = page
+        .evaluate_value(&load_js_script(options.inline_frames, !options.disable_shadow_dom))
+        .await
+        .map_err(|e| DocserError::ExtractionFailed(e.to_string()))?;
+    if html.ends_with(COMPOSED_HTML_TRUNCATED) {
+        html.truncate(html.len() - COMPOSED_HTML_TRUNCATED.len());
+        warn!(
+            "{} exceeds the serializer's limits ({} levels deep or {} nodes); content past them was left out",
+            redact_credentials(url),
+            COMPOSED_HTML_MAX_DEPTH,
+            COMPOSED_HTML_MAX_NODES
+        );
+    }
+    Ok(html)
+}
+
+// Clicks Google Programmable Search's page link for page_num and waits for the result list to
+// reload. Returns false when the click fails or the target page never shows up.
+async fn goto_google_cse_page(page: &Page, page_num: u32) -> bool {
+    const MAX_WAIT_MS: u64 = 10000;
+    const LOADING_JS: &str = "!!document.querySelector('.gsc-control-wrapper-cse.gsc-loading-fade')";
+
+    // Get current page number to verify navigation worked
+    let current_page: String = page
+        .evaluate_value("document.querySelector('.gsc-cursor-current-page')?.textContent")
+        .await
+        .unwrap_or_else(|_| "-1".to_string());
+    debug!("Currently on page {}, trying to navigate to page {}", current_page, page_num);
+
+    // Click the target page number
+    let locator = page
+        .locator(&format!(".gsc-cursor-page:nth-child({})", page_num))
+        .await;
+    if locator.click(Default::default()).await.is_err() {
+        return false;
+    }
+
+    // First wait for loading to start (might already be loading)
+    for _ in 0..(2000 / READY_CHECK_INTERVAL_MS) {
+        let loading: String = page.evaluate_value(LOADING_JS).await.unwrap_or_else(|_| "false".to_string());
+        if loading == "true" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(READY_CHECK_INTERVAL_MS)).await;
+    }
+
+    // Then wait for it to complete and verify we actually reached the target page
+    for _ in 0..(MAX_WAIT_MS / READY_CHECK_INTERVAL_MS) {
+        let loading: String = page.evaluate_value(LOADING_JS).await.unwrap_or_else(|_| "false".to_string());
+        if loading == "false" {
+            let new_page: String = page
+                .evaluate_value(&format!("document.querySelector('.gsc-cursor-page:nth-child({})')?.textContent", page_num))
+                .await
+                .unwrap_or_else(|_| "??".to_string());
+
+            if new_page == page_num.to_string() {
+                debug!("Successfully navigated to page {}", page_num);
+                // Additional stabilization delay
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                return true;
+            }
+            warn!("Expected page {} but ended up on page {}", page_num, new_page);
         }
+        tokio::time::sleep(Duration::from_millis(READY_CHECK_INTERVAL_MS)).await;
+    }
+    false
+}
+
+// Text a readiness indicator must hold before the page counts as ready
+#[derive(Clone, Copy)]
+struct ContentCheck {
+    // Characters of trimmed text the element needs
+    min_len: usize,
+    // Consecutive polls its text length must hold steady; 0 accepts the first poll over min_len
+    stable_polls: u32,
+}
+
+// A readiness element's text length across polls, deciding when it has settled
+struct Stabilization {
+    check: ContentCheck,
+    len: usize,
+    unchanged: u32,
+}
+
+impl Stabilization {
+    // Begins at the first poll's length; None while the text is too short to count at all
+    fn start(check: ContentCheck, len: usize) -> Option<Self> {
+        (len > check.min_len).then_some(Self { check, len, unchanged: 0 })
+    }
 
-        let result = SearchResult { links };
-        // TODO: Implement SQLite caching with TTL and eviction strategy
-        if result.links.is_empty() {
-            return Err("No links extracted".into());
+    // Records the next poll: an unchanged length extends the streak, any change restarts it
+    fn observe(&mut self, len: usize) {
+        if len == self.len {
+            self.unchanged += 1;
+        } else {
+            self.unchanged = 0;
+            self.len = len;
         }
-        Ok(serde_json::to_string(&result)?)
+    }
+
+    fn is_stable(&self) -> bool {
+        self.unchanged >= self.check.stable_polls
     }
 }
 
+// Polls JS indicator expressions (each evaluating to an element or null) until one matches,
+// returning false if none did within max_wait_ms. With a content_check the element must also hold
+// more than min_len characters of text, and its length must then stay the same for stable_polls
+// polls in a row (polling continues while it grows, up to max_wait_ms), so half-rendered SPA
+// content isn't mistaken for a ready page.
+async fn wait_for_any_indicator(
+    page: &Page,
+    indicators: &[String],
+    max_wait_ms: u64,
+    check_interval_ms: u64,
+    content_check: Option<ContentCheck>,
+) -> bool {
+    let check_interval_ms = check_interval_ms.max(1);
+    let interval = Duration::from_millis(check_interval_ms);
+    let deadline = Instant::now() + Duration::from_millis(max_wait_ms);
+
+    for attempt in 0..(max_wait_ms / check_interval_ms) {
+        for indicator in indicators {
+            let exists_str: String = page
+                .evaluate_value(&format!("!!({})", indicator))
+                .await
+                .unwrap_or_else(|_| "false".to_string());
+            if exists_str != "true" {
+                continue;
+            }
+
+            let Some(content_check) = content_check else {
+                debug!("Found '{}' on attempt {}", indicator, attempt + 1);
+                // Final stabilization delay
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                return true;
+            };
+
+            // Additional check: ensure the element has meaningful content
+            let length_js = format!("({}).textContent.trim().length", indicator);
+            let Some(mut stabilization) = Stabilization::start(content_check, text_length(page, &length_js).await) else {
+                continue;
+            };
+
+            // Stabilization: keep polling while the content is still growing (lazy sections,
+            // skeletons being replaced) until its length holds for stable_polls polls in a row
+            while !stabilization.is_stable() {
+                if Instant::now() >= deadline {
+                    debug!("'{}' still changing at the readiness timeout ({} chars)", indicator, stabilization.len);
+                    return true;
+                }
+                tokio::time::sleep(interval).await;
+                stabilization.observe(text_length(page, &length_js).await);
+            }
+            debug!(
+                "Page ready with stable content '{}' ({} chars) on attempt {}",
+                indicator,
+                stabilization.len,
+                attempt + 1
+            );
+            // Final stabilization delay
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            return true;
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+    false
+}
+
+// Scrolls to the bottom step by step so lazy content is rendered, until the page height stops
+// increasing or steps runs out
+async fn auto_scroll(page: &Page, steps: u32, delay: Duration) {
+    let mut last_height = 0;
+    for step in 1..=steps {
+        let height: u64 = page
+            .evaluate_value(SCROLL_TO_BOTTOM_JS)
+            .await
+            .ok()
+            .and_then(|height: String| height.parse().ok())
+            .unwrap_or(0);
+        if height <= last_height {
+            debug!("Page stopped growing after {} scrolls ({}px)", step - 1, height);
+            return;
+        }
+        last_height = height;
+        tokio::time::sleep(delay).await;
+    }
+    debug!("Scrolled {} times, page is {}px tall", steps, last_height);
+}
+
+async fn expand_collapsed(page: &Page) {
+    let expanded: usize = page
+        .evaluate_value(EXPAND_COLLAPSED_JS)
+        .await
+        .ok()
+        .and_then(|count: String| count.parse().ok())
+        .unwrap_or(0);
+    if expanded > 0 {
+        debug!("Expanded {} collapsed sections", expanded);
+        tokio::time::sleep(Duration::from_millis(EXPAND_SETTLE_MS)).await;
+    }
+}
+
+async fn text_length(page: &Page, length_js: &str) -> usize {
+    page.evaluate_value(length_js)
+        .await
+        .ok()
+        .and_then(|len: String| len.parse().ok())
+        .unwrap_or(0)
+}
+
+// Point by which an operation must finish, from DOCSER_OPERATION_BUDGET_MS. Checked between
+// phases (attempts, backoff, result pages) and used to cap the waits inside them.
+#[derive(Clone, Copy)]
+struct Deadline(Option<Instant>);
+
+impl Deadline {
+    fn after(budget: Duration) -> Self {
+        Self((!budget.is_zero()).then(|| Instant::now() + budget))
+    }
+
+    // None when the operation has no budget
+    fn remaining(&self) -> Option<Duration> {
+        self.0.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    fn expired(&self) -> bool {
+        self.remaining().is_some_and(|remaining| remaining.is_zero())
+    }
+
+    // Whether sleeping for delay would still leave time for another phase
+    fn allows(&self, delay: Duration) -> bool {
+        self.remaining().is_none_or(|remaining| remaining > delay)
+    }
+
+    // A wait shortened to what is left of the budget
+    fn cap_ms(&self, wait_ms: u64) -> u64 {
+        self.remaining()
+            .map_or(wait_ms, |remaining| wait_ms.min(remaining.as_millis() as u64))
+    }
+}
+
+fn wait_until(strategy: WaitStrategy) -> WaitUntil {
+    match strategy {
+        WaitStrategy::Load => WaitUntil::Load,
+        WaitStrategy::DomContentLoaded => WaitUntil::DomContentLoaded,
+        WaitStrategy::NetworkIdle => WaitUntil::NetworkIdle,
+    }
+}
+
+
+// Same-host http(s) pages only; assets like images and archives are never crawled
+fn is_crawlable(href: &str, host: &str, filter: &PathFilter) -> bool {
+    let Ok(url) = Url::parse(href) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https") && url.host_str() == Some(host) && !is_asset_url(&url) && filter.allows(&url)
+}
+
+// Whether two URLs are on the same site: same scheme, port and host, a leading www. aside
+fn same_site(a: &str, b: &str) -> bool {
+    let (Ok(a), Ok(b)) = (Url::parse(a), Url::parse(b)) else {
+        return false;
+    };
+    let host = |url: &Url| url.host_str().map(|host| host.trim_start_matches("www.").to_ascii_lowercase());
+    a.scheme() == b.scheme()
+        && a.port_or_known_default() == b.port_or_known_default()
+        && host(&a).is_some()
+        && host(&a) == host(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_hops_stay_on_the_site() {
+        assert!(same_site("https://docs.dev/guide", "https://www.docs.dev/guide/"));
+        assert!(!same_site("https://mirror.example/guide", "https://docs.dev/guide"));
+        assert!(!same_site("http://docs.dev/guide", "https://docs.dev/guide"));
+        assert!(!same_site("https://docs.dev:8443/guide", "https://docs.dev/guide"));
+    }
+
+    // Whether the polls settle, the first one starting the stabilization
+    fn settles(check: ContentCheck, polls: &[usize]) -> bool {
+        let Some((first, rest)) = polls.split_first() else {
+            return false;
+        };
+        let Some(mut stabilization) = Stabilization::start(check, *first) else {
+            return false;
+        };
+        for len in rest {
+            if stabilization.is_stable() {
+                break;
+            }
+            stabilization.observe(*len);
+        }
+        stabilization.is_stable()
+    }
+
+    const CHECK: ContentCheck = ContentCheck { min_len: 100, stable_polls: 3 };
+
+    #[test]
+    fn short_content_never_starts() {
+        assert!(Stabilization::start(CHECK, 100).is_none());
+        assert!(Stabilization::start(CHECK, 101).is_some());
+    }
+
+    #[test]
+    fn steady_length_settles_after_stable_polls() {
+        assert!(settles(CHECK, &[500, 500, 500, 500]));
+        assert!(!settles(CHECK, &[500, 500, 500]));
+    }
+
+    #[test]
+    fn growth_restarts_the_streak() {
+        assert!(!settles(CHECK, &[200, 200, 200, 900, 900]));
+        assert!(settles(CHECK, &[200, 200, 200, 900, 900, 900, 900]));
+    }
+
+    #[test]
+    fn zero_stable_polls_accepts_the_first_poll() {
+        assert!(settles(ContentCheck { min_len: 100, stable_polls: 0 }, &[150]));
+    }
+}
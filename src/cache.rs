@@ -0,0 +1,251 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Utc};
+use crate::models::{PageMetadata, ResponseInfo, ScrapedPage};
+
+// SQLite-backed cache of serialized search results, keyed by (site, query, max_page)
+pub struct SearchCache {
+    conn: Mutex<Connection>,
+    ttl: Duration,
+    max_rows: usize,
+}
+
+impl SearchCache {
+    pub fn open(path: &Path, ttl: Duration, max_rows: usize) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS search_results (
+                site TEXT NOT NULL,
+                query TEXT NOT NULL,
+                max_page INTEGER NOT NULL,
+                result TEXT NOT NULL,
+                inserted_at INTEGER NOT NULL,
+                PRIMARY KEY (site, query, max_page)
+            );
+            CREATE INDEX IF NOT EXISTS search_results_inserted_at ON search_results (inserted_at);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            ttl,
+            max_rows,
+        })
+    }
+
+    // Returns the cached result if it is younger than the TTL
+    pub fn get(&self, site: &str, query: &str, max_page: u32) -> rusqlite::Result<Option<String>> {
+        let cutoff = unix_now() - self.ttl.as_secs() as i64;
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.query_row(
+            "SELECT result FROM search_results WHERE site = ?1 AND query = ?2 AND max_page = ?3 AND inserted_at >= ?4",
+            params![site, query, max_page, cutoff],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+
+    // Stores a result, then drops expired rows and the oldest rows beyond the row cap
+    pub fn put(&self, site: &str, query: &str, max_page: u32, result: &str) -> rusqlite::Result<()> {
+        let now = unix_now();
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT OR REPLACE INTO search_results (site, query, max_page, result, inserted_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![site, query, max_page, result, now],
+        )?;
+        conn.execute(
+            "DELETE FROM search_results WHERE inserted_at < ?1",
+            params![now - self.ttl.as_secs() as i64],
+        )?;
+        conn.execute(
+            "DELETE FROM search_results WHERE rowid IN (
+                SELECT rowid FROM search_results ORDER BY inserted_at DESC LIMIT -1 OFFSET ?1
+            )",
+            params![self.max_rows as i64],
+        )?;
+        Ok(())
+    }
+}
+
+struct PageEntry {
+    page: ScrapedPage,
+    inserted_at: Instant,
+    last_access: Instant,
+}
+
+// In-memory LRU cache of scraped pages with a TTL; a max_entries of 0 disables it
+pub struct PageCache {
+    entries: Mutex<HashMap<String, PageEntry>>,
+    max_entries: usize,
+    ttl: Duration,
+}
+
+impl PageCache {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+            ttl,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<ScrapedPage> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.get_mut(key)?;
+        if entry.inserted_at.elapsed() > self.ttl {
+            entries.remove(key);
+            return None;
+        }
+        entry.last_access = Instant::now();
+        Some(entry.page.clone())
+    }
+
+    pub fn put(&self, key: String, page: ScrapedPage) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.insert(key, PageEntry { page, inserted_at: now, last_access: now });
+
+        while entries.len() > self.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
+
+// What an earlier sitemap crawl kept of a page: its markdown and the validators that tell whether
+// it changed since (the sitemap <lastmod> it was crawled at, the response's ETag/Last-Modified)
+pub struct RecrawlEntry {
+    pub lastmod: Option<DateTime<Utc>>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub final_url: String,
+    pub markdown: String,
+    pub content_hash: String,
+}
+
+impl RecrawlEntry {
+    pub fn new(page: &ScrapedPage, lastmod: Option<DateTime<Utc>>) -> Self {
+        Self {
+            lastmod,
+            etag: page.response.headers.get("etag").cloned(),
+            last_modified: page.response.headers.get("last-modified").cloned(),
+            final_url: page.response.final_url.clone(),
+            markdown: page.markdown.clone(),
+            content_hash: page.content_hash.clone(),
+        }
+    }
+
+    // The page as a crawl result; only the markdown and its response validators were kept
+    pub fn into_page(self, url: &str) -> ScrapedPage {
+        let headers = [("etag", self.etag), ("last-modified", self.last_modified)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect();
+        ScrapedPage {
+            url: url.to_string(),
+            canonical_url: None,
+            markdown: self.markdown,
+            content_hash: self.content_hash,
+            html: None,
+            toc: None,
+            blocks: None,
+            metadata: PageMetadata::default(),
+            response: ResponseInfo { status: 200, final_url: self.final_url, content_type: None, headers },
+            extraction: None,
+            reader: None,
+            links: Vec::new(),
+        }
+    }
+}
+
+// SQLite-backed record of pages from earlier sitemap crawls, so a re-crawl can skip pages that
+// haven't changed, across restarts too. Rows beyond max_rows are dropped, oldest crawl first.
+pub struct RecrawlCache {
+    conn: Mutex<Connection>,
+    max_rows: usize,
+}
+
+impl RecrawlCache {
+    pub fn open(path: &Path, max_rows: usize) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS recrawl_pages (
+                key TEXT PRIMARY KEY,
+                lastmod_ms INTEGER,
+                etag TEXT,
+                last_modified TEXT,
+                final_url TEXT NOT NULL,
+                markdown TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                crawled_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS recrawl_pages_crawled_at ON recrawl_pages (crawled_at);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            max_rows,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> rusqlite::Result<Option<RecrawlEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.query_row(
+            "SELECT lastmod_ms, etag, last_modified, final_url, markdown, content_hash FROM recrawl_pages WHERE key = ?1",
+            params![key],
+            |row| {
+                Ok(RecrawlEntry {
+                    lastmod: row.get::<_, Option<i64>>(0)?.and_then(DateTime::from_timestamp_millis),
+                    etag: row.get(1)?,
+                    last_modified: row.get(2)?,
+                    final_url: row.get(3)?,
+                    markdown: row.get(4)?,
+                    content_hash: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    // Stores an entry, then drops the oldest rows beyond the row cap
+    pub fn put(&self, key: &str, entry: &RecrawlEntry) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT OR REPLACE INTO recrawl_pages (key, lastmod_ms, etag, last_modified, final_url, markdown, content_hash, crawled_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                key,
+                entry.lastmod.map(|lastmod| lastmod.timestamp_millis()),
+                entry.etag,
+                entry.last_modified,
+                entry.final_url,
+                entry.markdown,
+                entry.content_hash,
+                unix_now()
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM recrawl_pages WHERE rowid IN (
+                SELECT rowid FROM recrawl_pages ORDER BY crawled_at DESC LIMIT -1 OFFSET ?1
+            )",
+            params![self.max_rows as i64],
+        )?;
+        Ok(())
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -2,10 +2,115 @@ use std::sync::OnceLock;
 
 static JS_SCRIPT: OnceLock<String> = OnceLock::new();
 
-pub fn load_js_script() -> &'static str {
-    JS_SCRIPT.get_or_init(|| {
+// Ceilings for the composed-HTML serializer so deeply nested or enormous DOMs can't hang the page;
+// anything past them is left out of the output
+pub const COMPOSED_HTML_MAX_DEPTH: u32 = 512;
+pub const COMPOSED_HTML_MAX_NODES: u32 = 200_000;
+
+// Appended by the serializer when a ceiling cut content, so the caller can report it
+pub const COMPOSED_HTML_TRUNCATED: &str = "<!--docser:truncated-->";
+
+// CSS selectors polled until one of them holds rendered text: content areas first, then the
+// mount points of common SPA frameworks (React/Next, Vue/Nuxt, Svelte, Angular)
+pub const DEFAULT_READY_SELECTORS: &[&str] = &[
+    "main, article, [role='main'], .post-content, .article-content, .content",
+    "#__next, #root, [data-reactroot]",
+    "#app, #__nuxt, #vue-app, #svelte",
+    "[ng-version]",
+];
+
+// Opens collapsed <details> and clicks aria-expanded="false" toggles (accordions, "show more"
+// sections) so lazily rendered content is in the DOM. Toggles in navigation, links that go
+// somewhere and form submit buttons are left alone so nothing navigates. Returns how many it opened.
+pub const EXPAND_COLLAPSED_JS: &str = r#"
+(() => {
+    let expanded = 0;
+    for (const details of document.querySelectorAll('details:not([open])')) {
+        details.open = true;
+        expanded++;
+    }
+    for (const toggle of document.querySelectorAll('[aria-expanded="false"]')) {
+        if (toggle.closest('nav, header, [role="navigation"], [role="menubar"], [role="banner"]')) continue;
+        const href = toggle.getAttribute('href');
+        if (toggle.tagName === 'A' && href && !href.startsWith('#')) continue;
+        if (toggle.closest('form') && (toggle.type === 'submit' || toggle.tagName === 'INPUT')) continue;
+        try {
+            toggle.click();
+            expanded++;
+        } catch (e) {}
+    }
+    return String(expanded);
+})()
+"#;
+
+// Scrolls to the bottom of the page and returns its height afterwards, as a string
+pub const SCROLL_TO_BOTTOM_JS: &str = r#"
+(() => {
+    const height = Math.max(document.body ? document.body.scrollHeight : 0, document.documentElement.scrollHeight);
+    window.scrollTo(0, height);
+    return String(height);
+})()
+"#;
+
+// Quotes a value as a JS string literal for embedding in evaluated scripts
+pub fn js_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+// Script that serializes the rendered page. inline_frames pulls the documents of same-origin
+// iframes into the output; cross-origin frames are never readable and stay empty. Without
+// expand_shadow_dom, shadow roots are ignored and elements serialize their light DOM children.
+// Output cut short by the ceilings ends with COMPOSED_HTML_TRUNCATED.
+pub fn load_js_script(inline_frames: bool, expand_shadow_dom: bool) -> String {
+    let script = JS_SCRIPT.get_or_init(|| {
         r#"
-(function() {
+(function(options) {
+    // Set when maxDepth or maxNodes cut content
+    let truncated = false;
+
+    // Elements that never have content or a closing tag
+    const VOID_ELEMENTS = new Set([
+        'area', 'base', 'br', 'col', 'embed', 'hr', 'img', 'input',
+        'link', 'meta', 'param', 'source', 'track', 'wbr',
+    ]);
+
+    /**
+     * Escapes an attribute value for use inside double quotes.
+     *
+     * @param {string} value - The raw attribute value.
+     * @returns {string} The escaped value.
+     */
+    function escapeAttribute(value) {
+        return value
+            .replace(/&/g, '&amp;')
+            .replace(/"/g, '&quot;')
+            .replace(/</g, '&lt;')
+            .replace(/>/g, '&gt;');
+    }
+
+    /**
+     * Escapes text content so markup shown as text (e.g. `&lt;div&gt;` in a code sample) stays text.
+     *
+     * @param {string} value - The raw text.
+     * @returns {string} The escaped text.
+     */
+    function escapeText(value) {
+        return value
+            .replace(/&/g, '&amp;')
+            .replace(/</g, '&lt;')
+            .replace(/>/g, '&gt;');
+    }
+
+    /**
+     * Serializes an element's attributes as ` name="value"` pairs.
+     *
+     * @param {Element} element - The element whose attributes to serialize.
+     * @returns {string} The serialized attributes.
+     */
+    function serializeAttributes(element) {
+        return Array.from(element.attributes).map(attr => ` ${attr.name}="${escapeAttribute(attr.value)}"`).join('');
+    }
+
     /**
      * Recursively extracts HTML from a root node, correctly processing open shadow DOMs,
      * filling <slot> elements, and ignoring <style> and <script> tags.
@@ -15,12 +120,21 @@ pub fn load_js_script() -> &'static str {
      */
     function getComposedHtml(root) {
         let html = '';
+        let visited = 0;
 
         /**
          * The recursive function that traverses the DOM.
          * @param {Node} node - The current node to process.
+         * @param {number} depth - Element nesting depth of the node.
          */
-        function traverseAndBuildHtml(node) {
+        function traverseAndBuildHtml(node, depth) {
+            // Stop descending past the depth ceiling and stop entirely past the node ceiling
+            if (depth > options.maxDepth || visited >= options.maxNodes) {
+                truncated = true;
+                return;
+            }
+            visited++;
+
             switch (node.nodeType) {
                 // Element node (e.g., <div>, <p>, <my-component>)
                 case Node.ELEMENT_NODE:
@@ -37,26 +151,49 @@ pub fn load_js_script() -> &'static str {
                         const assignedNodes = node.assignedNodes();
                         if (assignedNodes.length > 0) {
                             for (const assignedNode of assignedNodes) {
-                                traverseAndBuildHtml(assignedNode);
+                                traverseAndBuildHtml(assignedNode, depth);
                             }
                         } else {
                             for (const fallbackChild of node.childNodes) {
-                                traverseAndBuildHtml(fallbackChild);
+                                traverseAndBuildHtml(fallbackChild, depth);
                             }
                         }
                         return; // Stop processing this slot element
                     }
 
+                    // Same-origin iframes: inline the frame's body in place of the empty <iframe>.
+                    // contentDocument is null (or throws) for cross-origin frames, which are skipped.
+                    if (tagName === 'iframe' && options.inlineFrames) {
+                        let frameDocument = null;
+                        try {
+                            frameDocument = node.contentDocument;
+                        } catch (e) {
+                            frameDocument = null;
+                        }
+                        if (frameDocument && frameDocument.body) {
+                            html += '<div>';
+                            for (const frameChild of frameDocument.body.childNodes) {
+                                traverseAndBuildHtml(frameChild, depth + 1);
+                            }
+                            html += '</div>';
+                        }
+                        return;
+                    }
+
                     // For all other elements:
                     // Reconstruct the opening tag, including its attributes.
-                    const attributes = Array.from(node.attributes).map(attr => ` ${attr.name}="${attr.value}"`).join('');
-                    html += `<${tagName}${attributes}>`;
+                    html += `<${tagName}${serializeAttributes(node)}>`;
+
+                    // Void elements have no children and no closing tag
+                    if (VOID_ELEMENTS.has(tagName)) {
+                        return;
+                    }
 
-                    // If the element hosts a shadow root, traverse into the shadow DOM.
-                    // Otherwise, traverse its regular children (light DOM).
-                    const children = node.shadowRoot ? node.shadowRoot.childNodes : node.childNodes;
+                    // If the element hosts a shadow root (and expansion is on), traverse into the
+                    // shadow DOM. Otherwise, traverse its regular children (light DOM).
+                    const children = options.expandShadowDom && node.shadowRoot ? node.shadowRoot.childNodes : node.childNodes;
                     for (const child of children) {
-                        traverseAndBuildHtml(child);
+                        traverseAndBuildHtml(child, depth + 1);
                     }
 
                     // Add the closing tag.
@@ -65,7 +202,7 @@ pub fn load_js_script() -> &'static str {
 
                 // Text node
                 case Node.TEXT_NODE:
-                    html += node.textContent;
+                    html += escapeText(node.textContent);
                     break;
 
                 // Comment node
@@ -77,7 +214,7 @@ pub fn load_js_script() -> &'static str {
                 default:
                    if (node.childNodes) {
                        for (const child of node.childNodes) {
-                            traverseAndBuildHtml(child);
+                            traverseAndBuildHtml(child, depth);
                         }
                    }
                    break;
@@ -86,16 +223,25 @@ pub fn load_js_script() -> &'static str {
 
         // Start the traversal from the children of the provided root node.
         for (const child of root.childNodes) {
-            traverseAndBuildHtml(child);
+            traverseAndBuildHtml(child, 0);
         }
 
         return html;
     }
 
     // Get the full HTML by wrapping the composed content
-    const htmlAttributes = Array.from(document.documentElement.attributes).map(attr => ` ${attr.name}="${attr.value}"`).join('');
-    return `<html${htmlAttributes}>` + getComposedHtml(document.documentElement) + '</html>';
-})()
+    const html = `<html${serializeAttributes(document.documentElement)}>` + getComposedHtml(document.documentElement) + '</html>';
+    return truncated ? html + options.truncatedMarker : html;
+})
 "#.to_string()
-    })
+    });
+    format!(
+        "{}({{ inlineFrames: {}, expandShadowDom: {}, maxDepth: {}, maxNodes: {}, truncatedMarker: {} }})",
+        script.trim(),
+        inline_frames,
+        expand_shadow_dom,
+        COMPOSED_HTML_MAX_DEPTH,
+        COMPOSED_HTML_MAX_NODES,
+        js_string(COMPOSED_HTML_TRUNCATED)
+    )
 }
\ No newline at end of file
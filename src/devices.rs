@@ -0,0 +1,61 @@
+// Named devices for emulation, a small subset of Playwright's device descriptors covering the
+// layouts documentation sites switch between (desktop, phone, tablet)
+pub struct Device {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub device_scale_factor: f64,
+    pub is_mobile: bool,
+    pub user_agent: Option<&'static str>,
+}
+
+pub const DEVICES: &[Device] = &[
+    Device {
+        name: "Desktop",
+        width: 1280,
+        height: 800,
+        device_scale_factor: 1.0,
+        is_mobile: false,
+        user_agent: None,
+    },
+    Device {
+        name: "iPhone 13",
+        width: 390,
+        height: 844,
+        device_scale_factor: 3.0,
+        is_mobile: true,
+        user_agent: Some(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 15_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.0 Mobile/15E148 Safari/604.1",
+        ),
+    },
+    Device {
+        name: "Pixel 7",
+        width: 412,
+        height: 915,
+        device_scale_factor: 2.625,
+        is_mobile: true,
+        user_agent: Some(
+            "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36",
+        ),
+    },
+    Device {
+        name: "iPad Pro 11",
+        width: 834,
+        height: 1194,
+        device_scale_factor: 2.0,
+        is_mobile: true,
+        user_agent: Some(
+            "Mozilla/5.0 (iPad; CPU OS 15_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/15.0 Mobile/15E148 Safari/604.1",
+        ),
+    },
+];
+
+impl Device {
+    pub fn find(name: &str) -> Option<&'static Device> {
+        DEVICES.iter().find(|device| device.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn names() -> Vec<&'static str> {
+        DEVICES.iter().map(|device| device.name).collect()
+    }
+}
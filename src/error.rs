@@ -0,0 +1,169 @@
+use rmcp::ErrorData as McpError;
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DocserError {
+    #[error("Navigation failed: {0}")]
+    Navigation(String),
+    #[error("HTTP error: {0}")]
+    HttpStatus(u16),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Host not found: {0}")]
+    DnsFailure(String),
+    #[error("Connection refused: {0}")]
+    ConnectionRefused(String),
+    #[error("TLS error: {0}")]
+    TlsError(String),
+    #[error("No content: {0}")]
+    NoContent(String),
+    #[error("Extraction failed: {0}")]
+    ExtractionFailed(String),
+    #[error("Unsupported content type: {0}")]
+    UnsupportedContentType(String),
+    #[error("Disallowed by robots.txt: {0}")]
+    Disallowed(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Browser error: {0}")]
+    Browser(String),
+    #[error("Serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl DocserError {
+    // Classifies an error raised while navigating, where timeouts are the common failure. The
+    // network error codes differ per engine (Chromium net::ERR_*, Firefox NS_ERROR_*, WebKit prose).
+    pub fn navigation(e: playwright_rs::Error) -> Self {
+        let message = e.to_string();
+        if is_timeout(&message) {
+            Self::Timeout(message)
+        } else if contains_any(&message, DNS_FAILURE_MARKERS) {
+            Self::DnsFailure(message)
+        } else if contains_any(&message, CONNECTION_REFUSED_MARKERS) {
+            Self::ConnectionRefused(message)
+        } else if contains_any(&message, TLS_ERROR_MARKERS) {
+            Self::TlsError(message)
+        } else if message.contains("Download is starting") {
+            // Chromium turns navigations to PDFs and other attachments into downloads
+            Self::UnsupportedContentType("binary download (e.g. a PDF) can't be converted to markdown".into())
+        } else {
+            Self::Navigation(message)
+        }
+    }
+
+    // Whether trying again might succeed: network failures, refused connections (a server being
+    // restarted), timeouts, server-side HTTP errors and pages that rendered blank (usually a
+    // readiness wait that gave up too early). Unknown hosts, certificate problems, client errors
+    // such as a 404, and anything else about the content itself, are final.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Navigation(_)
+            | Self::Timeout(_)
+            | Self::ConnectionRefused(_)
+            | Self::Browser(_)
+            | Self::NoContent(_) => true,
+            Self::HttpStatus(status) => matches!(status, 408 | 425 | 429 | 500..=599),
+            _ => false,
+        }
+    }
+
+    // Stable machine-readable name, sent to MCP clients in the error data
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Navigation(_) => "navigation",
+            Self::HttpStatus(_) => "http_status",
+            Self::Timeout(_) => "timeout",
+            Self::DnsFailure(_) => "dns_failure",
+            Self::ConnectionRefused(_) => "connection_refused",
+            Self::TlsError(_) => "tls_error",
+            Self::NoContent(_) => "no_content",
+            Self::ExtractionFailed(_) => "extraction_failed",
+            Self::UnsupportedContentType(_) => "unsupported_content_type",
+            Self::Disallowed(_) => "disallowed",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::Browser(_) => "browser",
+            Self::Json(_) => "serialization",
+        }
+    }
+}
+
+impl From<playwright_rs::Error> for DocserError {
+    fn from(e: playwright_rs::Error) -> Self {
+        let message = e.to_string();
+        if is_timeout(&message) {
+            Self::Timeout(message)
+        } else {
+            Self::Browser(message)
+        }
+    }
+}
+
+impl From<DocserError> for McpError {
+    fn from(e: DocserError) -> Self {
+        let message = e.to_string();
+        let data = match &e {
+            DocserError::HttpStatus(status) => json!({ "kind": e.kind(), "status": status }),
+            _ => json!({ "kind": e.kind() }),
+        };
+        match e {
+            DocserError::HttpStatus(404) | DocserError::HttpStatus(410) | DocserError::NoContent(_) => {
+                McpError::resource_not_found(message, Some(data))
+            }
+            DocserError::UnsupportedContentType(_) | DocserError::InvalidArgument(_) => {
+                McpError::invalid_params(message, Some(data))
+            }
+            DocserError::Disallowed(_) => McpError::invalid_request(message, Some(data)),
+            _ => McpError::internal_error(message, Some(data)),
+        }
+    }
+}
+
+const DNS_FAILURE_MARKERS: &[&str] = &[
+    "ERR_NAME_NOT_RESOLVED",
+    "ERR_NAME_RESOLUTION_FAILED",
+    "NS_ERROR_UNKNOWN_HOST",
+    "Could not resolve host",
+    "hostname could not be found",
+];
+
+const CONNECTION_REFUSED_MARKERS: &[&str] = &[
+    "ERR_CONNECTION_REFUSED",
+    "NS_ERROR_CONNECTION_REFUSED",
+    "Could not connect to server",
+    "Connection refused",
+];
+
+const TLS_ERROR_MARKERS: &[&str] = &[
+    "ERR_CERT_",
+    "ERR_SSL_",
+    "SSL_ERROR_",
+    "SEC_ERROR_",
+    "MOZILLA_PKIX_ERROR_",
+    "SSL connect error",
+    // Engine wording only: a bare "certificate" would also match page URLs echoed in the message
+    "certificate verify failed",
+    "invalid certificate",
+];
+
+fn contains_any(message: &str, markers: &[&str]) -> bool {
+    markers.iter().any(|marker| message.contains(marker))
+}
+
+fn is_timeout(message: &str) -> bool {
+    message.contains("Timeout") || message.contains("timed out")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_mentioning_certificates_are_not_tls_errors() {
+        let timeout = "Timeout 30000ms exceeded navigating to https://docs.dev/docs/certificate-management";
+        assert!(!contains_any(timeout, TLS_ERROR_MARKERS));
+        assert!(contains_any("net::ERR_CERT_DATE_INVALID at https://docs.dev/", TLS_ERROR_MARKERS));
+        assert!(contains_any("SSL connect error: certificate verify failed", TLS_ERROR_MARKERS));
+    }
+}
@@ -0,0 +1,261 @@
+use scraper::{ElementRef, Html, Node};
+use crate::models::ContentBlock;
+use super::code::language_of;
+use super::dom::{BLOCK_ELEMENTS, SKIPPED_ELEMENTS, belongs_to, collapse_whitespace};
+
+// Walks extracted HTML in document order and emits typed blocks (headings with their level,
+// paragraphs, code with its language, lists, tables, quotes), so chunkers can keep sections intact
+pub fn to_blocks(html: &str) -> Vec<ContentBlock> {
+    let fragment = Html::parse_fragment(html);
+    let mut walker = BlockWalker::default();
+    walker.walk(fragment.root_element());
+    walker.flush_paragraph();
+    walker.blocks
+}
+
+#[derive(Default)]
+struct BlockWalker {
+    blocks: Vec<ContentBlock>,
+    // Inline text met directly inside a container, e.g. <div>Some <b>text</b></div>
+    paragraph: String,
+}
+
+impl BlockWalker {
+    fn walk(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.paragraph.push_str(text),
+                Node::Element(_) => {
+                    if let Some(child_element) = ElementRef::wrap(child) {
+                        self.visit(child_element);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn visit(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED_ELEMENTS.contains(&name) {
+            return;
+        }
+        if !BLOCK_ELEMENTS.contains(&name) {
+            // Inline elements just continue the running paragraph
+            if name == "br" {
+                self.paragraph.push(' ');
+            }
+            self.paragraph.push_str(&element.text().collect::<String>());
+            return;
+        }
+
+        self.flush_paragraph();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = inline_text(element);
+                if !text.is_empty() {
+                    self.blocks.push(ContentBlock::Heading {
+                        level: name[1..].parse().unwrap_or(1),
+                        text,
+                        id: element.value().id().map(str::to_string),
+                    });
+                }
+            }
+            "p" => self.push_paragraph(inline_text(element)),
+            "pre" => {
+                let code = element.text().collect::<String>();
+                let code = code.trim_start_matches('\n').trim_end();
+                if !code.is_empty() {
+                    let language = language_of(&element).or_else(|| {
+                        element
+                            .descendants()
+                            .filter_map(ElementRef::wrap)
+                            .find(|e| e.value().name() == "code")
+                            .and_then(|code| language_of(&code))
+                    });
+                    self.blocks.push(ContentBlock::Code { language, code: code.to_string() });
+                }
+            }
+            "ul" | "ol" => {
+                let items: Vec<String> = element
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|item| item.value().name() == "li")
+                    .map(inline_text)
+                    .filter(|item| !item.is_empty())
+                    .collect();
+                if !items.is_empty() {
+                    self.blocks.push(ContentBlock::List { ordered: name == "ol", items });
+                }
+            }
+            "table" => {
+                let rows: Vec<Vec<String>> = element
+                    .descendants()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|row| row.value().name() == "tr" && belongs_to(*row, element))
+                    .map(|row| {
+                        row.children()
+                            .filter_map(ElementRef::wrap)
+                            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                            .map(inline_text)
+                            .collect::<Vec<String>>()
+                    })
+                    .filter(|cells| !cells.is_empty())
+                    .collect();
+                if !rows.is_empty() {
+                    self.blocks.push(ContentBlock::Table { rows });
+                }
+            }
+            "blockquote" => {
+                let text = inline_text(element);
+                if !text.is_empty() {
+                    self.blocks.push(ContentBlock::Quote { text });
+                }
+            }
+            "hr" => {}
+            _ => {
+                self.walk(element);
+                self.flush_paragraph();
+            }
+        }
+    }
+
+    fn flush_paragraph(&mut self) {
+        let text = collapse_whitespace(&std::mem::take(&mut self.paragraph));
+        self.push_paragraph(text);
+    }
+
+    fn push_paragraph(&mut self, text: String) {
+        if !text.is_empty() {
+            self.blocks.push(ContentBlock::Paragraph { text });
+        }
+    }
+}
+
+fn inline_text(element: ElementRef) -> String {
+    collapse_whitespace(&element.text().collect::<String>())
+}
+
+// Caps the blocks at max_chars characters of text, like truncate_chars does for markdown: the
+// block crossing the limit is cut (lists and tables keep their items and rows that fit) and a
+// "[truncated]" paragraph marks the cut
+pub fn truncate_blocks(blocks: &mut Vec<ContentBlock>, max_chars: usize) {
+    let mut remaining = max_chars;
+    let Some(index) = blocks.iter().position(|block| {
+        let len = block_chars(block);
+        if len > remaining {
+            return true;
+        }
+        remaining -= len;
+        false
+    }) else {
+        return;
+    };
+
+    let mut keep = index;
+    if cut_block(&mut blocks[index], remaining) {
+        keep += 1;
+    }
+    blocks.truncate(keep);
+    blocks.push(ContentBlock::Paragraph { text: "[truncated]".to_string() });
+}
+
+fn block_chars(block: &ContentBlock) -> usize {
+    match block {
+        ContentBlock::Heading { text, .. } | ContentBlock::Paragraph { text } | ContentBlock::Quote { text } => {
+            text.chars().count()
+        }
+        ContentBlock::Code { code, .. } => code.chars().count(),
+        ContentBlock::List { items, .. } => items.iter().map(|item| item.chars().count()).sum(),
+        ContentBlock::Table { rows } => rows.iter().flatten().map(|cell| cell.chars().count()).sum(),
+    }
+}
+
+// Shortens the block to at most max_chars characters; false when nothing of it fits
+fn cut_block(block: &mut ContentBlock, max_chars: usize) -> bool {
+    match block {
+        ContentBlock::Heading { text, .. }
+        | ContentBlock::Paragraph { text }
+        | ContentBlock::Quote { text }
+        | ContentBlock::Code { code: text, .. } => {
+            if let Some((byte_index, _)) = text.char_indices().nth(max_chars) {
+                text.truncate(byte_index);
+            }
+            !text.is_empty()
+        }
+        ContentBlock::List { items, .. } => {
+            let mut remaining = max_chars;
+            let fitting = items.iter().take_while(|item| fits(item.chars().count(), &mut remaining)).count();
+            items.truncate(fitting);
+            !items.is_empty()
+        }
+        ContentBlock::Table { rows } => {
+            let mut remaining = max_chars;
+            let fitting = rows
+                .iter()
+                .take_while(|row| fits(row.iter().map(|cell| cell.chars().count()).sum(), &mut remaining))
+                .count();
+            rows.truncate(fitting);
+            !rows.is_empty()
+        }
+    }
+}
+
+fn fits(len: usize, remaining: &mut usize) -> bool {
+    if len > *remaining {
+        return false;
+    }
+    *remaining -= len;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(text: &str) -> ContentBlock {
+        ContentBlock::Paragraph { text: text.to_string() }
+    }
+
+    fn texts(blocks: &[ContentBlock]) -> Vec<String> {
+        blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Paragraph { text } => text.clone(),
+                ContentBlock::List { items, .. } => items.join("|"),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks_within_the_limit_are_untouched() {
+        let mut blocks = vec![paragraph("12345"), paragraph("678")];
+        truncate_blocks(&mut blocks, 8);
+        assert_eq!(texts(&blocks), ["12345", "678"]);
+    }
+
+    #[test]
+    fn crossing_block_is_cut_and_marked() {
+        let mut blocks = vec![paragraph("12345"), paragraph("6789"), paragraph("later")];
+        truncate_blocks(&mut blocks, 7);
+        assert_eq!(texts(&blocks), ["12345", "67", "[truncated]"]);
+    }
+
+    #[test]
+    fn lists_keep_whole_items_that_fit() {
+        let mut blocks = vec![
+            paragraph("123"),
+            ContentBlock::List { ordered: false, items: vec!["ab".into(), "cd".into(), "ef".into()] },
+        ];
+        truncate_blocks(&mut blocks, 8);
+        assert_eq!(texts(&blocks), ["123", "ab|cd", "[truncated]"]);
+    }
+
+    #[test]
+    fn block_with_nothing_left_is_dropped() {
+        let mut blocks = vec![paragraph("12345"), ContentBlock::List { ordered: true, items: vec!["long item".into()] }];
+        truncate_blocks(&mut blocks, 6);
+        assert_eq!(texts(&blocks), ["12345", "[truncated]"]);
+    }
+}
@@ -0,0 +1,103 @@
+use scraper::{ElementRef, Node, Selector};
+use url::Url;
+
+// Elements serialized without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// Attributes holding a URL, resolved by the absolutizing writers
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "data-src"];
+
+pub fn parse_selectors<'a>(selectors: impl IntoIterator<Item = &'a &'a str>) -> Vec<Selector> {
+    selectors
+        .into_iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .collect()
+}
+
+// Serializes an element and its subtree, dropping every element (at any depth) that matches one
+// of the exclusions, together with its descendants. Comments are dropped as well.
+pub fn write_filtered(element: ElementRef, exclusions: &[Selector], out: &mut String) {
+    write_element(element, exclusions, None, out);
+}
+
+// Serializes an element and its subtree with relative href/src/data-src values (paths, ../
+// segments, protocol-relative //cdn URLs and #anchors) resolved against base. Only attributes of
+// parsed elements are rewritten, so markup shown as text, like an escaped <a href> in a code
+// sample, stays exactly as it was.
+pub fn write_absolutized(element: ElementRef, base: &Url, out: &mut String) {
+    write_element(element, &[], Some(base), out);
+}
+
+// write_absolutized for the children of an element, e.g. the wrapper of a parsed fragment
+pub fn write_absolutized_children(parent: ElementRef, base: &Url, out: &mut String) {
+    write_children(parent, &[], Some(base), out);
+}
+
+fn write_element(element: ElementRef, exclusions: &[Selector], base: Option<&Url>, out: &mut String) {
+    if exclusions.iter().any(|selector| selector.matches(&element)) {
+        return;
+    }
+
+    let value = element.value();
+    out.push('<');
+    out.push_str(value.name());
+    for (name, attr_value) in value.attrs() {
+        let absolute = base
+            .filter(|_| URL_ATTRIBUTES.contains(&name.to_ascii_lowercase().as_str()))
+            .and_then(|base| absolute_url(attr_value, base));
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        escape_into(absolute.as_deref().unwrap_or(attr_value), true, out);
+        out.push('"');
+    }
+    out.push('>');
+    if VOID_ELEMENTS.contains(&value.name()) {
+        return;
+    }
+
+    write_children(element, exclusions, base, out);
+
+    out.push_str("</");
+    out.push_str(value.name());
+    out.push('>');
+}
+
+fn write_children(parent: ElementRef, exclusions: &[Selector], base: Option<&Url>, out: &mut String) {
+    for child in parent.children() {
+        match child.value() {
+            Node::Element(_) => {
+                if let Some(child_element) = ElementRef::wrap(child) {
+                    write_element(child_element, exclusions, base, out);
+                }
+            }
+            Node::Text(text) => escape_into(text, false, out),
+            _ => {}
+        }
+    }
+}
+
+// The value resolved against base; None when it is empty or already absolute (https:, mailto:,
+// data:, ...)
+fn absolute_url(value: &str, base: &Url) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || Url::parse(trimmed).is_ok() {
+        return None;
+    }
+    base.join(trimmed).ok().map(|url| url.to_string())
+}
+
+fn escape_into(text: &str, attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' if !attribute => out.push_str("&lt;"),
+            '>' if !attribute => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Selector};
+
+lazy_static! {
+    static ref PRE_BLOCK: Regex = Regex::new(r"(?is)<pre\b[^>]*>.*?</pre>").unwrap();
+    static ref PLACEHOLDER: Regex = Regex::new(r"DOCSERCODEBLOCK(\d+)END").unwrap();
+}
+
+struct CodeBlock {
+    language: Option<String>,
+    code: String,
+}
+
+// Code blocks pulled out of the HTML before markdown conversion, so the converter can't
+// re-indent them or drop their language, and put back as fenced blocks afterwards
+pub struct CodeBlocks {
+    blocks: Vec<CodeBlock>,
+}
+
+impl CodeBlocks {
+    // Replaces every <pre> with a placeholder paragraph the converter passes through verbatim
+    pub fn extract(html: &str) -> (String, Self) {
+        let mut blocks = Vec::new();
+        let replaced = PRE_BLOCK.replace_all(html, |caps: &Captures| {
+            let Some(block) = parse_block(&caps[0]) else {
+                return caps[0].to_string();
+            };
+            blocks.push(block);
+            format!("<p>DOCSERCODEBLOCK{}END</p>", blocks.len() - 1)
+        });
+        (replaced.into_owned(), Self { blocks })
+    }
+
+    pub fn restore(&self, markdown: &str) -> String {
+        if self.blocks.is_empty() {
+            return markdown.to_string();
+        }
+        PLACEHOLDER
+            .replace_all(markdown, |caps: &Captures| {
+                match caps[1].parse::<usize>().ok().and_then(|index| self.blocks.get(index)) {
+                    Some(block) => fence(block),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+fn parse_block(pre_html: &str) -> Option<CodeBlock> {
+    let fragment = Html::parse_fragment(pre_html);
+    let pre_selector = Selector::parse("pre").ok()?;
+    let code_selector = Selector::parse("code").ok()?;
+    let pre = fragment.select(&pre_selector).next()?;
+
+    let language = language_of(&pre).or_else(|| pre.select(&code_selector).find_map(|code| language_of(&code)));
+    let code = pre.text().collect::<String>();
+    let code = code.trim_start_matches('\n').trim_end().to_string();
+    if code.is_empty() {
+        return None;
+    }
+    Some(CodeBlock { language, code })
+}
+
+// Reads the language from class="language-x" / "lang-x" / "highlight-x" or data-lang / data-language
+pub(super) fn language_of(element: &ElementRef) -> Option<String> {
+    let value = element.value();
+    let from_data = value.attr("data-lang").or_else(|| value.attr("data-language"));
+    let from_class = || {
+        value.classes().find_map(|class| {
+            ["language-", "lang-", "highlight-"]
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix))
+        })
+    };
+    let language = from_data.or_else(from_class)?.trim().to_lowercase();
+    let valid = !language.is_empty()
+        && language.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_' | '.'));
+    valid.then_some(language)
+}
+
+// Uses a fence longer than any backtick run inside the code so it can't be closed early
+fn fence(block: &CodeBlock) -> String {
+    let longest_run = block
+        .code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        block.language.as_deref().unwrap_or(""),
+        block.code,
+        fence
+    )
+}
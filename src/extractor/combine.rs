@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use crate::models::SitePage;
+use super::fence::{FenceLine, FenceTracker};
+use super::toc::{slugify, unique_slug};
+
+// Level of the heading every page gets in the combined document; its own headings start one below
+const PAGE_HEADING_LEVEL: usize = 2;
+
+// Joins the pages of a site crawl into one markdown document: a table of contents linking to each
+// page, then every page under a heading with its title and source URL, separated by rules. Pages
+// the start page links to come first, in that order, then the rest in crawl order. Each page's
+// headings are shifted so its top level sits just below its page heading. Duplicates and failed
+// pages are left out.
+pub fn combine_pages(pages: &[SitePage]) -> String {
+    let mut included: Vec<(&SitePage, &str)> = pages
+        .iter()
+        .filter_map(|page| page.markdown.as_deref().map(|markdown| (page, markdown)))
+        .collect();
+    // Stable, so pages outside the navigation keep their crawl order
+    included.sort_by_key(|(page, _)| (page.nav_position.is_none(), page.nav_position));
+
+    // Every heading takes its anchor in document order, as renderers assign them, so a page title
+    // repeating an earlier page's subheading still gets its own
+    let mut used_slugs: HashMap<String, usize> = HashMap::from([("contents".to_string(), 1)]);
+    let mut contents = String::from("# Contents\n");
+    let mut sections = Vec::new();
+    for (page, markdown) in included {
+        let title = page_title(page);
+        let anchor = unique_slug(&slugify(&title), &mut used_slugs);
+        contents.push_str(&format!("\n- [{}](#{})", escape_link_text(&title), anchor));
+        let body = nest_headings(markdown, &title);
+        register_headings(&body, &mut used_slugs);
+        sections.push(format!(
+            "{} {}\n\nSource: <{}>\n\n{}",
+            "#".repeat(PAGE_HEADING_LEVEL),
+            title,
+            page.url,
+            body.trim()
+        ));
+    }
+
+    let mut document = contents;
+    for section in sections {
+        document.push_str("\n\n---\n\n");
+        document.push_str(&section);
+    }
+    document
+}
+
+// Claims the anchors of the headings in markdown, outside code fences
+fn register_headings(markdown: &str, used_slugs: &mut HashMap<String, usize>) {
+    let mut fences = FenceTracker::default();
+    for line in markdown.lines() {
+        if fences.classify(line) == FenceLine::Prose
+            && let Some(level) = heading_level(line)
+        {
+            unique_slug(&slugify(heading_text(line, level)), used_slugs);
+        }
+    }
+}
+
+// Backslash-escapes what would end or nest the text of a [text](url) link, e.g. "Array[T]"
+fn escape_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn page_title(page: &SitePage) -> String {
+    let title = page.title.as_deref().map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "));
+    match title {
+        Some(title) if !title.is_empty() => title,
+        _ => page.url.clone(),
+    }
+}
+
+// Shifts the ATX headings outside code fences so the shallowest becomes PAGE_HEADING_LEVEL + 1
+// (capped at 6). A first heading repeating the page title is dropped, since the page heading
+// already says it.
+fn nest_headings(markdown: &str, title: &str) -> String {
+    let mut fences = FenceTracker::default();
+    let lines: Vec<(&str, Option<usize>)> = markdown
+        .lines()
+        .map(|line| {
+            let prose = fences.classify(line) == FenceLine::Prose;
+            (line, if prose { heading_level(line) } else { None })
+        })
+        .collect();
+    let repeated_title = lines
+        .iter()
+        .position(|(line, _)| !line.trim().is_empty())
+        .filter(|index| lines[*index].1.is_some_and(|level| heading_text(lines[*index].0, level).eq_ignore_ascii_case(title.trim())));
+    let shallowest = lines
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| Some(*index) != repeated_title)
+        .filter_map(|(_, (_, level))| *level)
+        .min()
+        .unwrap_or(1);
+
+    let mut out = Vec::with_capacity(lines.len());
+    for (index, (line, level)) in lines.iter().enumerate() {
+        if Some(index) == repeated_title {
+            continue;
+        }
+        let Some(level) = level else {
+            out.push(line.to_string());
+            continue;
+        };
+        let nested = (level - shallowest + PAGE_HEADING_LEVEL + 1).min(6);
+        let text = heading_text(line, *level);
+        out.push(format!("{} {}", "#".repeat(nested), text));
+    }
+    out.join("\n")
+}
+
+fn heading_text(line: &str, level: usize) -> &str {
+    line[level..].trim().trim_end_matches('#').trim()
+}
+
+// 1 to 6 for an ATX heading line ("## Title"), None otherwise
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[hashes..];
+    ((1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' '))).then_some(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, title: &str, markdown: &str, nav_position: Option<usize>) -> SitePage {
+        SitePage {
+            url: url.to_string(),
+            depth: 0,
+            title: Some(title.to_string()),
+            markdown: Some(markdown.to_string()),
+            content_hash: None,
+            duplicate_of: None,
+            error: None,
+            nav_position,
+        }
+    }
+
+    #[test]
+    fn pages_follow_nav_order_then_crawl_order() {
+        let pages = vec![
+            page("https://d.dev/", "Home", "Welcome", Some(0)),
+            page("https://d.dev/extra", "Extra", "Unlisted", None),
+            page("https://d.dev/b", "Second", "B", Some(2)),
+            page("https://d.dev/a", "First", "A", Some(1)),
+        ];
+        let document = combine_pages(&pages);
+        let order: Vec<usize> = ["## Home", "## First", "## Second", "## Extra"]
+            .iter()
+            .map(|heading| document.find(heading).unwrap())
+            .collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(document.starts_with("# Contents\n\n- [Home](#home)\n- [First](#first)"));
+        assert!(document.contains("## First\n\nSource: <https://d.dev/a>\n\nA"));
+    }
+
+    #[test]
+    fn headings_nest_below_the_page_heading() {
+        let markdown = "# Install\n\nIntro\n\n## Linux\n\n```sh\n# not a heading\n```\n\n### Arch";
+        assert_eq!(
+            nest_headings(markdown, "Install"),
+            "\nIntro\n\n### Linux\n\n```sh\n# not a heading\n```\n\n#### Arch"
+        );
+    }
+
+    #[test]
+    fn page_title_after_a_same_named_subheading_gets_its_own_anchor() {
+        let pages = vec![
+            page("https://d.dev/install", "Install", "Intro\n\n## Linux\n\nSteps", Some(0)),
+            page("https://d.dev/linux", "Linux", "Kernel notes", Some(1)),
+        ];
+        let document = combine_pages(&pages);
+        assert!(document.contains("- [Linux](#linux-1)"));
+    }
+
+    #[test]
+    fn brackets_in_titles_are_escaped_in_the_contents() {
+        let document = combine_pages(&[page("https://d.dev/array", "Array[T] \\ slices", "Body", Some(0))]);
+        assert!(document.contains("- [Array\\[T\\] \\\\ slices](#arrayt--slices)"));
+    }
+
+    #[test]
+    fn duplicates_and_failures_are_left_out() {
+        let mut duplicate = page("https://d.dev/copy", "Copy", "", None);
+        duplicate.markdown = None;
+        duplicate.duplicate_of = Some("https://d.dev/".into());
+        let document = combine_pages(&[page("https://d.dev/", "Home", "Hi", Some(0)), duplicate]);
+        assert!(!document.contains("Copy"));
+    }
+}
@@ -0,0 +1,115 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use super::clean;
+use super::dom::SKIPPED_ELEMENTS;
+
+// Containers considered as the content root
+const CANDIDATES: &str = "div, section, article, main, td";
+
+// Children whose text counts towards their parent container: the blocks prose is written in
+const TEXT_BLOCKS: &[&str] = &[
+    "p", "pre", "blockquote", "ul", "ol", "dl", "table", "h1", "h2", "h3", "h4", "h5", "h6", "figure",
+];
+
+// Containers scoring below this many characters of prose aren't worth returning
+const MIN_SCORE: f64 = 250.0;
+
+// Picks the container holding the most prose, in the spirit of classic text-density extractors:
+// each candidate scores the text of its own paragraphs, headings, lists, code and tables (not
+// that of nested containers, so the page wrapper doesn't win by containing everything), scaled
+// down by the share of its text that sits inside links, which is how navigation looks.
+// Returns the winner's HTML with the exclusions stripped.
+pub fn densest_container(document: &Html, exclusions: &[Selector]) -> Option<String> {
+    let candidates = Selector::parse(CANDIDATES).ok()?;
+    let links = Selector::parse("a").ok()?;
+
+    let (best, score) = document
+        .select(&candidates)
+        .filter(|element| !exclusions.iter().any(|selector| selector.matches(element)))
+        .map(|element| {
+            let own_text = own_text_len(element);
+            let total_text = text_len(element).max(1);
+            let link_text: usize = element.select(&links).map(text_len).sum();
+            let link_density = link_text.min(total_text) as f64 / total_text as f64;
+            (element, own_text as f64 * (1.0 - link_density))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if score < MIN_SCORE {
+        return None;
+    }
+
+    let mut html = String::new();
+    clean::write_filtered(best, exclusions, &mut html);
+    Some(html)
+}
+
+// Text directly inside the element or in its prose blocks
+fn own_text_len(element: ElementRef) -> usize {
+    element
+        .children()
+        .map(|child| match child.value() {
+            Node::Text(text) => text.trim().chars().count(),
+            Node::Element(value) if TEXT_BLOCKS.contains(&value.name()) => ElementRef::wrap(child).map_or(0, text_len),
+            _ => 0,
+        })
+        .sum()
+}
+
+// Visible characters in the subtree, whitespace runs counted once
+fn text_len(element: ElementRef) -> usize {
+    let mut len = 0;
+    for node in element.descendants() {
+        let Node::Text(text) = node.value() else {
+            continue;
+        };
+        let skipped = node
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take_while(|ancestor| ancestor.id() != element.id())
+            .any(|ancestor| {
+                // Menus inside a candidate are link lists, not prose
+                let name = ancestor.value().name();
+                name == "nav" || SKIPPED_ELEMENTS.contains(&name)
+            });
+        if !skipped {
+            len += text.split_whitespace().map(|word| word.chars().count() + 1).sum::<usize>();
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROSE: &str = "Docser renders documentation pages in a real browser and converts the main content to markdown, dropping navigation, footers and other chrome along the way.";
+
+    fn densest(html: &str) -> Option<String> {
+        densest_container(&Html::parse_document(html), &[])
+    }
+
+    #[test]
+    fn link_heavy_container_loses_to_article() {
+        let links: String = (0..40).map(|i| format!("<li><a href=\"/p{}\">Reference page number {}</a></li>", i, i)).collect();
+        let html = format!(
+            "<body><div id=\"menu\"><ul>{}</ul></div><div id=\"article\"><p>{}</p><p>{}</p></div></body>",
+            links, PROSE, PROSE
+        );
+        let winner = densest(&html).unwrap();
+        assert!(winner.contains("id=\"article\""));
+    }
+
+    #[test]
+    fn too_little_prose_is_none() {
+        assert_eq!(densest("<body><div><p>Just a short note.</p></div></body>"), None);
+    }
+
+    #[test]
+    fn wrapper_does_not_win_by_containing_everything() {
+        let html = format!(
+            "<body><div id=\"wrapper\"><div id=\"content\"><p>{}</p><p>{}</p></div><p>Footer line.</p></div></body>",
+            PROSE, PROSE
+        );
+        let winner = densest(&html).unwrap();
+        assert!(winner.starts_with("<div id=\"content\""));
+    }
+}
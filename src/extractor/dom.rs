@@ -0,0 +1,23 @@
+use scraper::ElementRef;
+
+// Elements whose text is never content
+pub const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg", "button"];
+
+// Elements that start a new paragraph, ending any run of loose inline content
+pub const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "details", "div", "dl", "dt", "fieldset",
+    "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr",
+    "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table", "tr", "ul",
+];
+
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Rows of nested tables belong to those tables, not the outer one
+pub fn belongs_to(row: ElementRef, table: ElementRef) -> bool {
+    row.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|e| e.value().name() == "table")
+        .is_some_and(|owner| owner.id() == table.id())
+}
@@ -0,0 +1,41 @@
+// What a markdown line is with respect to fenced code blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenceLine {
+    // Outside any fence
+    Prose,
+    // The ``` or ~~~ line opening a fence
+    Open,
+    // Inside a fence, its closing line included
+    Code,
+}
+
+// Follows fenced code blocks through markdown read line by line, for rewrites that must leave
+// code exactly as it is
+#[derive(Default)]
+pub struct FenceTracker {
+    open: Option<String>,
+}
+
+impl FenceTracker {
+    pub fn classify(&mut self, line: &str) -> FenceLine {
+        let marker: String = line
+            .trim_start()
+            .chars()
+            .take_while(|c| *c == '`' || *c == '~')
+            .collect();
+        match &self.open {
+            Some(open) => {
+                // A fence closes with the same character, at least as long as the one that opened it
+                if marker.len() >= open.len() && marker.chars().all(|c| open.starts_with(c)) && line.trim() == marker {
+                    self.open = None;
+                }
+                FenceLine::Code
+            }
+            None if marker.len() >= 3 && (marker.chars().all(|c| c == '`') || marker.chars().all(|c| c == '~')) => {
+                self.open = Some(marker);
+                FenceLine::Open
+            }
+            None => FenceLine::Prose,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Selector};
+use super::dom::collapse_whitespace;
+
+lazy_static! {
+    static ref FIGURE: Regex = Regex::new(r"(?is)<figure\b[^>]*>.*?</figure\s*>").unwrap();
+    static ref IMG_TAG: Regex = Regex::new(r"(?i)<img\b[^>]*>").unwrap();
+    static ref PLACEHOLDER: Regex = Regex::new(r"DOCSERIMAGE(\d+)END").unwrap();
+}
+
+// Images pulled out before markdown conversion and put back as ![alt](src), since the converters
+// drop alt text and <figcaption>. Runs on HTML whose src attributes are already absolute.
+pub struct Images {
+    images: Vec<String>,
+}
+
+impl Images {
+    pub fn extract(html: &str) -> (String, Self) {
+        let mut images = Vec::new();
+
+        // Figures first, so the caption stays with its image; figures without an image are left alone
+        let html = FIGURE.replace_all(html, |caps: &Captures| {
+            let Some(figure) = figure_markdown(&caps[0]) else {
+                return caps[0].to_string();
+            };
+            images.push(figure);
+            format!("<p>DOCSERIMAGE{}END</p>", images.len() - 1)
+        });
+
+        // Remaining images are inline, so no paragraph around the placeholder (they may sit inside links)
+        let html = IMG_TAG.replace_all(&html, |caps: &Captures| {
+            let fragment = Html::parse_fragment(&caps[0]);
+            let image = Selector::parse("img")
+                .ok()
+                .and_then(|selector| fragment.select(&selector).next().and_then(|img| image_markdown(&img, None)));
+            match image {
+                Some(image) => {
+                    images.push(image);
+                    format!("DOCSERIMAGE{}END", images.len() - 1)
+                }
+                None => String::new(),
+            }
+        });
+        (html.into_owned(), Self { images })
+    }
+
+    pub fn restore(&self, markdown: &str) -> String {
+        if self.images.is_empty() {
+            return markdown.to_string();
+        }
+        PLACEHOLDER
+            .replace_all(markdown, |caps: &Captures| {
+                match caps[1].parse::<usize>().ok().and_then(|index| self.images.get(index)) {
+                    Some(image) => image.clone(),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+// Every image in the figure followed by its caption in italics on its own line
+fn figure_markdown(figure_html: &str) -> Option<String> {
+    let fragment = Html::parse_fragment(figure_html);
+    let img_selector = Selector::parse("img").ok()?;
+    let caption_selector = Selector::parse("figcaption").ok()?;
+
+    let caption = fragment
+        .select(&caption_selector)
+        .next()
+        .map(|caption| collapse_whitespace(&caption.text().collect::<String>()))
+        .filter(|caption| !caption.is_empty());
+    let images: Vec<String> = fragment
+        .select(&img_selector)
+        .filter_map(|img| image_markdown(&img, caption.as_deref()))
+        .collect();
+    if images.is_empty() {
+        return None;
+    }
+
+    let mut markdown = images.join("\n\n");
+    if let Some(caption) = caption {
+        markdown.push_str(&format!("\n\n*{}*", caption.replace('*', "\\*")));
+    }
+    Some(markdown)
+}
+
+// ![alt](src), with the title attribute or the figure caption standing in for a missing alt.
+// Lazy-loaded images keep their real URL in data-src; inline data: images are reduced to their alt.
+fn image_markdown(img: &ElementRef, caption: Option<&str>) -> Option<String> {
+    let value = img.value();
+    let alt = [value.attr("alt"), value.attr("title"), caption]
+        .into_iter()
+        .flatten()
+        .map(collapse_whitespace)
+        .find(|alt| !alt.is_empty())
+        .unwrap_or_default()
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+    let src = [value.attr("src"), value.attr("data-src")]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|src| !src.is_empty() && !src.starts_with("data:"));
+
+    match src {
+        Some(src) => Some(format!("![{}]({})", alt, src.replace(' ', "%20").replace(')', "%29"))),
+        None if !alt.is_empty() => Some(alt),
+        None => None,
+    }
+}
@@ -0,0 +1,132 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use crate::models::LinkStyle;
+use super::fence::{FenceLine, FenceTracker};
+
+lazy_static! {
+    // [text](url "title") with an optional leading ! for images. The text may hold escaped brackets
+    // and a whole image, so linked images ([![alt](src)](href)) match as one link.
+    static ref INLINE_LINK: Regex = Regex::new(
+        r#"(!?)\[((?:[^\[\]\\]|\\.|!\[(?:[^\[\]\\]|\\.)*\]\([^()]*\))*)\]\((<[^<>]*>|[^()\s]*(?:\([^()\s]*\)[^()\s]*)*)(?:\s+"(?:[^"\\]|\\.)*")?\)"#
+    )
+    .unwrap();
+    // A reference written by restyle_links: [text][3]
+    static ref REFERENCE: Regex = Regex::new(r"\]\[(\d+)\]").unwrap();
+}
+
+// URLs pulled out of the markdown by the footnotes style, numbered from 1
+#[derive(Debug, Default)]
+pub struct LinkReferences {
+    urls: Vec<String>,
+}
+
+impl LinkReferences {
+    // Appends the definitions ([3]: url) of the references still present in markdown, so a body
+    // truncated after restyling never ends with a list of URLs nothing points to, nor loses the
+    // list to the cut
+    pub fn append_to(&self, markdown: &mut String) {
+        if self.urls.is_empty() {
+            return;
+        }
+        let used: HashSet<usize> = REFERENCE
+            .captures_iter(markdown)
+            .filter_map(|caps| caps[1].parse().ok())
+            .collect();
+        let definitions: Vec<String> = self
+            .urls
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| used.contains(&(index + 1)))
+            .map(|(index, url)| format!("[{}]: {}", index + 1, url))
+            .collect();
+        if !definitions.is_empty() {
+            markdown.push_str("\n\n");
+            markdown.push_str(&definitions.join("\n"));
+        }
+    }
+}
+
+// Rewrites the inline links of converted markdown: TextOnly keeps just the link text, Footnotes
+// turns them into reference links ([text][1]), one number per distinct URL, and returns the URLs
+// for LinkReferences::append_to. Images, code spans and fenced code blocks are left alone.
+pub fn restyle_links(markdown: &str, style: LinkStyle) -> (String, LinkReferences) {
+    let mut references = LinkReferences::default();
+    if style == LinkStyle::Keep {
+        return (markdown.to_string(), references);
+    }
+
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut rewrite = |caps: &Captures| -> String {
+        if !caps[1].is_empty() {
+            return caps[0].to_string();
+        }
+        let text = &caps[2];
+        match style {
+            LinkStyle::Footnotes => {
+                let url = caps[3].trim_start_matches('<').trim_end_matches('>').to_string();
+                let number = *numbers.entry(url.clone()).or_insert_with(|| {
+                    references.urls.push(url);
+                    references.urls.len()
+                });
+                format!("[{}][{}]", text, number)
+            }
+            _ => text.to_string(),
+        }
+    };
+
+    let mut out = Vec::new();
+    let mut fences = FenceTracker::default();
+    for line in markdown.lines() {
+        if fences.classify(line) != FenceLine::Prose {
+            out.push(line.to_string());
+            continue;
+        }
+
+        // Odd segments between backticks are code spans
+        let rewritten: Vec<String> = line
+            .split('`')
+            .enumerate()
+            .map(|(index, segment)| {
+                if index % 2 == 1 {
+                    segment.to_string()
+                } else {
+                    INLINE_LINK.replace_all(segment, &mut rewrite).into_owned()
+                }
+            })
+            .collect();
+        out.push(rewritten.join("`"));
+    }
+    (out.join("\n"), references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_only_drops_urls_but_keeps_images_and_code() {
+        let markdown = "See [the guide](https://a.dev/guide) and ![logo](https://a.dev/l.png).\n`[x](y)`\n```\n[kept](https://a.dev)\n```";
+        let (body, _) = restyle_links(markdown, LinkStyle::TextOnly);
+        assert_eq!(
+            body,
+            "See the guide and ![logo](https://a.dev/l.png).\n`[x](y)`\n```\n[kept](https://a.dev)\n```"
+        );
+    }
+
+    #[test]
+    fn footnotes_number_distinct_urls() {
+        let (mut body, references) =
+            restyle_links("[a](https://a.dev) [b](https://b.dev) [again](https://a.dev)", LinkStyle::Footnotes);
+        references.append_to(&mut body);
+        assert_eq!(body, "[a][1] [b][2] [again][1]\n\n[1]: https://a.dev\n[2]: https://b.dev");
+    }
+
+    #[test]
+    fn footnotes_only_define_references_that_survive_truncation() {
+        let (mut body, references) = restyle_links("[a](https://a.dev) then [b](https://b.dev)", LinkStyle::Footnotes);
+        body.truncate(body.find(" then").unwrap());
+        references.append_to(&mut body);
+        assert_eq!(body, "[a][1]\n\n[1]: https://a.dev");
+    }
+}
@@ -1,87 +1,213 @@
+mod blocks;
+mod clean;
+mod code;
+mod combine;
+mod density;
+mod dom;
+mod fence;
+mod image;
+mod language;
+mod link_style;
+mod pager;
+mod section;
+mod table;
+mod text;
+mod toc;
 
 use scraper::{Html, Selector};
 use lazy_static::lazy_static;
-use readability_rust::Readability;
+use readability_rust::{Readability, ReadabilityOptions};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use url::Url;
+use crate::models::{ExtractionStrategy, Link, PageMetadata, ReaderMetadata};
+use code::CodeBlocks;
+use fence::{FenceLine, FenceTracker};
+use image::Images;
+use table::Tables;
+pub use blocks::{to_blocks, truncate_blocks};
+pub use combine::combine_pages;
+pub use language::detect_language;
+pub use link_style::restyle_links;
+pub use section::section_for_anchor;
+pub use text::to_text;
+pub use toc::table_of_contents;
+use tracing::warn;
 
 struct Framework {
+    name: &'static str,
     main_container: &'static str,
     text_content_selector: &'static str,
     exclusions: &'static [&'static str],
+    // Drop Previous/Next link blocks that no exclusion selector can pin down
+    strip_pagers: bool,
 }
 
+// A framework profile as written in the DOCSER_FRAMEWORK_PROFILES file
+#[derive(Deserialize)]
+struct FrameworkProfile {
+    name: Option<String>,
+    main_container: String,
+    text_content_selector: String,
+    #[serde(default)]
+    exclusions: Vec<String>,
+    #[serde(default)]
+    strip_pagers: bool,
+}
+
+// Profiles loaded at startup; tried before the built-in FRAMEWORKS
+static CUSTOM_FRAMEWORKS: OnceLock<Vec<Framework>> = OnceLock::new();
+
 lazy_static! {
     static ref FRAMEWORKS: Vec<Framework> = vec![
-        // Docusaurus v2/v3
         Framework {
+            name: "Docusaurus v2/v3",
             main_container: "main",
             text_content_selector: "article.markdown",
             exclusions: &[".pagination-nav", ".theme-doc-toc-desktop", ".theme-doc-sidebar-container", ".hash-link"],
+            strip_pagers: false,
         },
-        // Sphinx (RTD)
         Framework {
+            name: "Sphinx (RTD)",
             main_container: ".wy-nav-content",
             text_content_selector: "[itemprop='articleBody']",
             exclusions: &[".wy-nav-side", ".rst-footer-buttons", "a.headerlink"],
+            strip_pagers: false,
         },
-        // Sphinx (Alabaster)
         Framework {
+            name: "Sphinx (Alabaster)",
             main_container: "div.body",
             text_content_selector: "div.body",
             exclusions: &[".sphinxsidebar", ".link-header"],
+            strip_pagers: false,
         },
-        // MkDocs (Material)
         Framework {
+            name: "MkDocs (Material)",
             main_container: ".md-main",
             text_content_selector: ".md-content__inner",
             exclusions: &[".md-sidebar", ".md-footer", ".md-header", ".md-clipboard"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Astro Starlight",
+            main_container: "main[data-pagefind-body]",
+            text_content_selector: ".sl-markdown-content",
+            exclusions: &[".right-sidebar", "starlight-toc", ".pagination-links", ".sl-anchor-link"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Mintlify",
+            main_container: "#content-area",
+            text_content_selector: "#content-area",
+            exclusions: &["#table-of-contents", "#sidebar", "#navbar", "#pagination", "#footer"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Docsify",
+            main_container: "section.content",
+            text_content_selector: "article.markdown-section",
+            exclusions: &[".sidebar", ".docsify-pagination-container", ".app-nav"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "VitePress",
+            main_container: ".VPDoc",
+            text_content_selector: ".vp-doc",
+            exclusions: &[".VPDocAside", ".VPDocFooter", ".VPSidebar", ".VPLocalNav", ".header-anchor"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "VuePress",
+            main_container: ".theme-container",
+            text_content_selector: ".theme-default-content",
+            exclusions: &[".sidebar", ".page-nav", ".page-meta", ".header-anchor"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Antora",
+            main_container: "main.article",
+            text_content_selector: "article.doc",
+            exclusions: &[".toolbar", ".toc", ".nav-container", ".pagination", "a.anchor"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Jekyll (Just the Docs)",
+            main_container: ".main-content-wrap",
+            text_content_selector: "#main-content",
+            exclusions: &[".side-bar", ".breadcrumb-nav", ".anchor-heading", "#markdown-toc", ".site-footer"],
+            strip_pagers: false,
+        },
+        Framework {
+            name: "Fumadocs",
+            main_container: "#nd-page",
+            text_content_selector: "#nd-page",
+            exclusions: &["#nd-toc", "#nd-sidebar", "#nd-subnav", "footer"],
+            strip_pagers: false,
         },
-        // GitBook (Legacy)
         Framework {
+            name: "GitBook (Legacy)",
             main_container: ".page-inner",
             text_content_selector: ".page-inner section",
             exclusions: &[".book-summary", ".book-header"],
+            strip_pagers: false,
         },
-        // GitBook (Cloud)
         Framework {
-            main_container: "main",
+            name: "GitBook (Cloud)",
+            // Its layout is a bare <main> like countless other sites, so the generator tag is what
+            // identifies it
+            main_container: "meta[name='generator'][content^='GitBook']",
             text_content_selector: "main",
-            exclusions: &["nav", "div[class*='sidebar']"],
+            // The page outline sits in an <aside> inside <main>; the Previous/Next cards at the
+            // bottom only carry utility classes, hence strip_pagers
+            exclusions: &["nav", "aside", "div[class*='sidebar']", "[data-testid='table-of-contents']"],
+            strip_pagers: true,
         },
-        // Hugo (General)
         Framework {
+            name: "Hugo (General)",
             main_container: "main",
             text_content_selector: ".content, .post-content",
             exclusions: &["header", "footer", ".menu"],
+            strip_pagers: false,
         },
-        // Nextra
         Framework {
+            name: "Nextra",
             main_container: "main",
             text_content_selector: "main",
             exclusions: &["nav", "footer", ".nextra-sidebar-container"],
+            strip_pagers: false,
         },
-        // NY Times
         Framework {
+            name: "NY Times",
             main_container: "#site-content",
             text_content_selector: "section[data-testid='story-content']",
             exclusions: &["#site-content-skip", "[data-testid='related-links']", "[data-testid='newsletter-signup']"],
+            strip_pagers: false,
         },
-        // BBC News
         Framework {
+            name: "BBC News",
             main_container: "[role='main']",
             text_content_selector: "[data-component='text-block']",
             exclusions: &["[role='complementary']", ".bbc-1151pbn"],
+            strip_pagers: false,
         },
-        // CNN
         Framework {
+            name: "CNN",
             main_container: ".article__content",
             text_content_selector: ".Paragraph__component",
             exclusions: &[".el-spoke-story", ".zn-body__read-more", ".ad-container"],
+            strip_pagers: false,
         },
-        // Reuters
         Framework {
+            name: "Reuters",
             main_container: "main",
             text_content_selector: "[class*='article-body__content']",
             exclusions: &["[data-testid='sidebar']", "nav", ".read-next-container"],
+            strip_pagers: false,
         },
     ];
 
@@ -96,67 +222,238 @@ lazy_static! {
     ];
 }
 
-pub fn extract_content(html: &str) -> String {
-    let document = Html::parse_document(html);
+// Loads extra framework profiles from a JSON array of {name, main_container, text_content_selector,
+// exclusions, strip_pagers} objects. Profiles with an invalid selector are skipped with a warning. Can only
+// take effect once per process; returns how many profiles were registered.
+pub fn load_framework_profiles(path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let profiles: Vec<FrameworkProfile> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
 
-    // Tier 1: Framework Detection
-    for framework in FRAMEWORKS.iter() {
-        if let Some(content) = apply_framework_extraction(&document, framework) {
-            return content;
+    let mut frameworks = Vec::new();
+    for (index, profile) in profiles.into_iter().enumerate() {
+        let invalid = std::iter::once(&profile.main_container)
+            .chain(std::iter::once(&profile.text_content_selector))
+            .chain(profile.exclusions.iter())
+            .find(|selector| Selector::parse(selector).is_err());
+        if let Some(selector) = invalid {
+            warn!("Skipping framework profile #{} in {}: invalid selector '{}'", index, path.display(), selector);
+            continue;
         }
+        // Profiles live for the whole process, so leaking them keeps Framework's &'static fields
+        let name = profile.name.unwrap_or_else(|| format!("custom #{}", index));
+        frameworks.push(Framework {
+            name: Box::leak(name.into_boxed_str()),
+            main_container: Box::leak(profile.main_container.into_boxed_str()),
+            text_content_selector: Box::leak(profile.text_content_selector.into_boxed_str()),
+            exclusions: Box::leak(
+                profile
+                    .exclusions
+                    .into_iter()
+                    .map(|exclusion| &*Box::leak(exclusion.into_boxed_str()))
+                    .collect::<Vec<&'static str>>()
+                    .into_boxed_slice(),
+            ),
+            strip_pagers: profile.strip_pagers,
+        });
     }
 
-    // Tier 2: Semantic Discovery
-    if let Some(content) = apply_semantic_extraction(&document) {
-        return content;
+    let count = frameworks.len();
+    CUSTOM_FRAMEWORKS
+        .set(frameworks)
+        .map_err(|_| "framework profiles were already loaded")?;
+    Ok(count)
+}
+
+// Which extraction path produced the content, for diagnosing poor markdown. The readability tier
+// also carries the article metadata it found along the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionTier {
+    Framework(&'static str),
+    Semantic(&'static str),
+    Density,
+    Readability(ReaderMetadata),
+    FullPage,
+}
+
+impl ExtractionTier {
+    // The readability tier's article metadata, None for other tiers or when it found none
+    pub fn into_reader(self) -> Option<ReaderMetadata> {
+        match self {
+            ExtractionTier::Readability(reader) if reader != ReaderMetadata::default() => Some(reader),
+            _ => None,
+        }
     }
+}
+
+impl fmt::Display for ExtractionTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractionTier::Framework(name) => write!(f, "framework:{}", name),
+            ExtractionTier::Semantic(selector) => write!(f, "semantic:{}", selector),
+            ExtractionTier::Density => write!(f, "density"),
+            ExtractionTier::Readability(_) => write!(f, "readability"),
+            ExtractionTier::FullPage => write!(f, "full_page"),
+        }
+    }
+}
+
+// The crawl pipeline minus the browser: isolates the content of an already rendered page and
+// converts it to markdown, with URLs resolved against page_url when given. Returns the markdown
+// and the tier that produced it, so profiles can be tuned against saved HTML.
+pub fn extract_markdown(
+    html: &str,
+    page_url: Option<&str>,
+    exclusions: &[Selector],
+    strategy: &[ExtractionStrategy],
+    converter: MarkdownConverter,
+) -> (String, ExtractionTier) {
+    let (content, tier) = extract_content(html, exclusions, strategy);
+    let content = match page_url {
+        Some(page_url) => absolutize_urls(&content, &base_url(html, page_url)),
+        None => content,
+    };
+    (to_markdown(&content, converter), tier)
+}
+
+// Names of every extraction profile, custom profiles first, in the order they are tried
+pub fn framework_names() -> Vec<&'static str> {
+    let custom_frameworks = CUSTOM_FRAMEWORKS.get().map(Vec::as_slice).unwrap_or_default();
+    custom_frameworks
+        .iter()
+        .chain(FRAMEWORKS.iter())
+        .map(|framework| framework.name)
+        .collect()
+}
+
+// Name of the first documentation framework (custom profiles first) whose main container is on
+// the page, whether or not its content selector matches anything
+pub fn detect_framework(html: &str) -> Option<&'static str> {
+    let document = Html::parse_document(html);
+    let custom_frameworks = CUSTOM_FRAMEWORKS.get().map(Vec::as_slice).unwrap_or_default();
+    custom_frameworks
+        .iter()
+        .chain(FRAMEWORKS.iter())
+        .find(|framework| {
+            Selector::parse(framework.main_container)
+                .is_ok_and(|selector| document.select(&selector).next().is_some())
+        })
+        .map(|framework| framework.name)
+}
 
-    // Tier 3: Heuristic Fallback (using readability-rust crate, as it's already a dependency)
-    if let Ok(mut parser) = Readability::new(html, None) {
-        if let Some(article) = parser.parse() {
-            if let Some(content) = article.content {
-                return content;
+// Selectors stripped from extracted content: the built-in EXCLUSION_SELECTORS unless disabled,
+// plus per-request extras. Invalid extras are skipped with a warning rather than failing the crawl.
+pub fn exclusion_selectors(extra: &[String], include_defaults: bool) -> Vec<Selector> {
+    let mut selectors = if include_defaults {
+        clean::parse_selectors(EXCLUSION_SELECTORS.iter())
+    } else {
+        Vec::new()
+    };
+    for selector in extra {
+        match Selector::parse(selector) {
+            Ok(parsed) => selectors.push(parsed),
+            Err(e) => warn!("Ignoring invalid exclusion selector '{}': {}", selector, e),
+        }
+    }
+    selectors
+}
+
+// Isolates the article content of a page, trying the tiers in the given order (normally
+// ExtractionStrategy::DEFAULT_ORDER). The exclusions are stripped from framework, semantic and
+// density matches; the readability tier does its own boilerplate removal.
+pub fn extract_content(html: &str, exclusions: &[Selector], strategy: &[ExtractionStrategy]) -> (String, ExtractionTier) {
+    let document = Html::parse_document(html);
+
+    for tier in strategy {
+        let extracted = match tier {
+            ExtractionStrategy::Framework => apply_framework_tier(&document, exclusions),
+            ExtractionStrategy::Semantic => apply_semantic_extraction(&document, exclusions)
+                .map(|(content, selector)| (content, ExtractionTier::Semantic(selector))),
+            ExtractionStrategy::Density => {
+                density::densest_container(&document, exclusions).map(|content| (content, ExtractionTier::Density))
             }
+            ExtractionStrategy::Readability => apply_readability(html),
+        };
+        if let Some(extracted) = extracted {
+            return extracted;
         }
     }
 
     // Fallback to returning the original HTML if no specific content can be extracted
-    html.to_string()
+    (html.to_string(), ExtractionTier::FullPage)
+}
+
+// Framework Detection, user-supplied profiles first
+fn apply_framework_tier(document: &Html, exclusions: &[Selector]) -> Option<(String, ExtractionTier)> {
+    let custom_frameworks = CUSTOM_FRAMEWORKS.get().map(Vec::as_slice).unwrap_or_default();
+    custom_frameworks.iter().chain(FRAMEWORKS.iter()).find_map(|framework| {
+        apply_framework_extraction(document, framework, exclusions)
+            .map(|content| (content, ExtractionTier::Framework(framework.name)))
+    })
+}
+
+// Heuristic fallback (using readability-rust crate, as it's already a dependency). The article's
+// title, byline and excerpt come out of the same parse and are kept with the tier.
+fn apply_readability(html: &str) -> Option<(String, ExtractionTier)> {
+    let mut parser = Readability::new(html, Some(ReadabilityOptions {
+        char_threshold: 500,
+        debug: false,
+        ..Default::default()
+    }))
+    .ok()?;
+    let article = parser.parse()?;
+    let clean = |value: Option<String>| {
+        value
+            .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|value| !value.is_empty())
+    };
+    let reader = ReaderMetadata {
+        title: clean(article.title),
+        byline: clean(article.byline),
+        excerpt: clean(article.excerpt),
+    };
+    Some((article.content?, ExtractionTier::Readability(reader)))
 }
 
-fn apply_framework_extraction(document: &Html, framework: &Framework) -> Option<String> {
+// Outer HTML of every element matching a caller-supplied selector, skipping matches nested in an
+// earlier one. None when nothing matches; an unparsable selector is an error.
+pub fn select_subtree(html: &str, selector: &str) -> Result<Option<String>, String> {
+    let parsed = Selector::parse(selector).map_err(|e| format!("Invalid selector '{}': {}", selector, e))?;
+    let document = Html::parse_document(html);
+    let mut subtree = String::new();
+    let mut captured = HashSet::new();
+    for element in document.select(&parsed) {
+        if element.ancestors().any(|ancestor| captured.contains(&ancestor.id())) {
+            continue;
+        }
+        captured.insert(element.id());
+        subtree.push_str(&element.html());
+    }
+    Ok(if subtree.is_empty() { None } else { Some(subtree) })
+}
+
+fn apply_framework_extraction(document: &Html, framework: &Framework, exclusions: &[Selector]) -> Option<String> {
     let main_container_selector = Selector::parse(framework.main_container).ok()?;
     
     if document.select(&main_container_selector).next().is_some() {
         let content_selector = Selector::parse(framework.text_content_selector).ok()?;
-        let mut content_html = String::new();
+        let mut exclusions = exclusions.to_vec();
+        exclusions.extend(clean::parse_selectors(framework.exclusions.iter()));
+        let mut cleaned_html = String::new();
+        let mut captured = HashSet::new();
 
+        // select() yields in document order, so a match nested inside an earlier match (e.g.
+        // GitBook's nested sections) is always seen after it and would only repeat its text
         for element in document.select(&content_selector) {
-            content_html.push_str(&element.html());
-        }
-
-        if !content_html.is_empty() {
-            let fragment = Html::parse_fragment(&content_html);
-            let mut cleaned_html = String::new();
-
-            for node in fragment.root_element().children() {
-                if let Some(element_ref) = scraper::ElementRef::wrap(node) {
-                    let mut a = true;
-                    for selector_str in framework.exclusions.iter().chain(EXCLUSION_SELECTORS.iter()) {
-                        if let Ok(selector) = Selector::parse(selector_str) {
-                            if selector.matches(&element_ref) {
-                                a = false;
-                                break;
-                            }
-                        }
-                    }
-                    if a {
-                        cleaned_html.push_str(&element_ref.html());
-                    }
-                } else if let Some(text) = node.value().as_text() {
-                    cleaned_html.push_str(text.text.as_ref());
-                }
+            if element.ancestors().any(|ancestor| captured.contains(&ancestor.id())) {
+                continue;
             }
+            captured.insert(element.id());
+            clean::write_filtered(element, &exclusions, &mut cleaned_html);
+        }
+
+        if framework.strip_pagers {
+            cleaned_html = pager::strip_pagers(&cleaned_html);
+        }
+        if !cleaned_html.is_empty() {
             return Some(cleaned_html);
         }
     }
@@ -164,36 +461,437 @@ fn apply_framework_extraction(document: &Html, framework: &Framework) -> Option<
     None
 }
 
-fn apply_semantic_extraction(document: &Html) -> Option<String> {
+fn apply_semantic_extraction(document: &Html, exclusions: &[Selector]) -> Option<(String, &'static str)> {
     let semantic_selectors = ["[itemprop='articleBody']", "[role='main']"];
     for selector_str in semantic_selectors.iter() {
         if let Ok(selector) = Selector::parse(selector_str) {
             if let Some(element) = document.select(&selector).next() {
-                let fragment = Html::parse_fragment(&element.html());
                 let mut cleaned_html = String::new();
-
-                for node in fragment.root_element().children() {
-                    if let Some(element_ref) = scraper::ElementRef::wrap(node) {
-                        let mut a = true;
-                        for selector_str in EXCLUSION_SELECTORS.iter() {
-                            if let Ok(selector) = Selector::parse(selector_str) {
-                                if selector.matches(&element_ref) {
-                                    a = false;
-                                    break;
-                                }
-                            }
-                        }
-                        if a {
-                            cleaned_html.push_str(&element_ref.html());
-                        }
-                    } else if let Some(text) = node.value().as_text() {
-                        cleaned_html.push_str(text.text.as_ref());
-                    }
-                }
-                return Some(cleaned_html);
+                clean::write_filtered(element, exclusions, &mut cleaned_html);
+                return Some((cleaned_html, *selector_str));
             }
         }
     }
     None
 }
 
+// Reduces a full document (doctype, <html>, <head>) to the contents of its <body>, so the
+// full-page fallback and raw mode don't leak <head> text such as the title into the markdown.
+// Fragments produced by the extraction tiers pass through untouched.
+fn strip_document_wrapper(html: &str) -> String {
+    if !is_full_document(html) {
+        return html.to_string();
+    }
+    let document = Html::parse_document(html);
+    Selector::parse("body")
+        .ok()
+        .and_then(|selector| document.select(&selector).next().map(|body| body.inner_html()))
+        .unwrap_or_else(|| html.to_string())
+}
+
+// Whether the HTML is a whole document (doctype, <html>, <head> or <body> first) rather than a
+// fragment cut out of one
+fn is_full_document(html: &str) -> bool {
+    let start = html.trim_start().get(..9).unwrap_or("").to_ascii_lowercase();
+    ["<html", "<!doctype", "<head", "<body"].iter().any(|tag| start.starts_with(tag))
+}
+
+// HTML-to-markdown backends. html2md is the long-standing default; htmd produces GFM-style
+// output and copes better with tables and nested lists on some sites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarkdownConverter {
+    #[default]
+    Html2md,
+    Htmd,
+}
+
+impl MarkdownConverter {
+    pub const ALL: &[MarkdownConverter] = &[MarkdownConverter::Html2md, MarkdownConverter::Htmd];
+
+    fn convert(&self, html: &str) -> String {
+        match self {
+            Self::Html2md => html2md::parse_html(html),
+            Self::Htmd => htmd::convert(html).unwrap_or_else(|e| {
+                warn!("htmd conversion failed, falling back to html2md: {}", e);
+                html2md::parse_html(html)
+            }),
+        }
+    }
+}
+
+impl FromStr for MarkdownConverter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "html2md" => Ok(Self::Html2md),
+            "htmd" => Ok(Self::Htmd),
+            other => Err(format!("Unknown markdown converter '{}', expected html2md or htmd", other)),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownConverter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Html2md => "html2md",
+            Self::Htmd => "htmd",
+        };
+        f.write_str(name)
+    }
+}
+
+// Converts extracted HTML to markdown. Tables, code blocks and images bypass the converter: tables
+// come back as GFM pipe tables, code as fenced blocks tagged with their language, images with their
+// alt text and figure caption.
+pub fn to_markdown(html: &str, converter: MarkdownConverter) -> String {
+    let html = strip_document_wrapper(html);
+    let (html, tables) = Tables::extract(&html, converter);
+    let (html, code_blocks) = CodeBlocks::extract(&html);
+    let (html, images) = Images::extract(&html);
+    tidy_whitespace(&tables.restore(&code_blocks.restore(&images.restore(&converter.convert(&html)))))
+}
+
+// Trims trailing whitespace, collapses runs of blank lines to one and drops leading and trailing
+// blank lines. Fenced code blocks are left exactly as they are.
+fn tidy_whitespace(markdown: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut fences = FenceTracker::default();
+    for line in markdown.lines() {
+        match fences.classify(line) {
+            FenceLine::Code => {
+                lines.push(line);
+                continue;
+            }
+            FenceLine::Open => {
+                lines.push(line.trim_end());
+                continue;
+            }
+            FenceLine::Prose => {}
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+// Reads <title>, the meta description, the canonical link (made absolute) and og:title
+pub fn extract_metadata(html: &str, base_url: &str) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let first_attr = |selector: &str, attr: &str| -> Option<String> {
+        let selector = Selector::parse(selector).ok()?;
+        document
+            .select(&selector)
+            .filter_map(|element| element.value().attr(attr))
+            .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+            .find(|value| !value.is_empty())
+    };
+
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(|element| element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty());
+    let canonical = first_attr("link[rel='canonical']", "href").map(|href| {
+        Url::parse(base_url)
+            .and_then(|base| base.join(&href))
+            .map(|url| url.to_string())
+            .unwrap_or(href)
+    });
+
+    PageMetadata {
+        title,
+        description: first_attr("meta[name='description']", "content")
+            .or_else(|| first_attr("meta[property='og:description']", "content")),
+        canonical,
+        og_title: first_attr("meta[property='og:title']", "content"),
+        // The declared language; scrape_page falls back to detecting it from the text
+        language: first_attr("html[lang]", "lang").and_then(|lang| language::primary_subtag(&lang)),
+    }
+}
+
+// Hex SHA-256 of the markdown, stable across runs so clients can dedup between crawls
+pub fn content_hash(markdown: &str) -> String {
+    format!("{:x}", Sha256::digest(markdown.as_bytes()))
+}
+
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+// Token estimate by the usual rule of thumb for English text with BPE tokenizers: one token per
+// four characters, rounded up
+pub fn approx_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+// Cuts text down to max_chars characters (never inside a multi-byte character) and marks the cut
+pub fn truncate_chars(text: &mut String, max_chars: usize) {
+    if let Some((byte_index, _)) = text.char_indices().nth(max_chars) {
+        text.truncate(byte_index);
+        text.push_str("\n\n[truncated]");
+    }
+}
+
+// The URL relative references in the document resolve against: its <base href> if present,
+// otherwise page_url
+pub fn base_url(html: &str, page_url: &str) -> String {
+    Url::parse(page_url)
+        .map(|page_base| document_base(&Html::parse_document(html), page_base).to_string())
+        .unwrap_or_else(|_| page_url.to_string())
+}
+
+fn document_base(document: &Html, page_base: Url) -> Url {
+    Selector::parse("base[href]")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .and_then(|element| element.value().attr("href"))
+        .and_then(|href| page_base.join(href).ok())
+        .unwrap_or(page_base)
+}
+
+// Rewrites relative href/src/data-src attributes (paths, ../ segments, protocol-relative //cdn URLs
+// and #anchors) to absolute URLs so the markdown still works once it leaves the page. Works on the
+// parsed DOM; text, including HTML shown in code samples, is never touched.
+pub fn absolutize_urls(html: &str, base_url: &str) -> String {
+    let Ok(base) = Url::parse(base_url) else {
+        return html.to_string();
+    };
+    let mut out = String::new();
+    if is_full_document(html) {
+        let document = Html::parse_document(html);
+        clean::write_absolutized(document.root_element(), &base, &mut out);
+    } else {
+        let fragment = Html::parse_fragment(html);
+        clean::write_absolutized_children(fragment.root_element(), &base, &mut out);
+    }
+    out
+}
+
+// Collects every <a href> as an absolute URL (fragment removed), skipping in-page anchors and
+// non-navigational schemes. A <base href> in the document takes precedence over base_url.
+pub fn extract_links(html: &str, base_url: &str) -> Vec<Link> {
+    let document = Html::parse_document(html);
+    let Ok(page_base) = Url::parse(base_url) else {
+        return Vec::new();
+    };
+    let base = document_base(&document, page_base);
+
+    let Ok(anchor_selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+
+    for anchor in document.select(&anchor_selector) {
+        let href = anchor.value().attr("href").unwrap_or("").trim();
+        if href.is_empty() || href.starts_with('#') {
+            continue;
+        }
+        let Ok(mut absolute) = base.join(href) else {
+            continue;
+        };
+        if !matches!(absolute.scheme(), "http" | "https") {
+            continue;
+        }
+        absolute.set_fragment(None);
+
+        let href = absolute.to_string();
+        if seen.insert(href.clone()) {
+            let text = anchor.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            links.push(Link { href, text, snippet: None });
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(html: &str) -> (String, ExtractionTier) {
+        extract_content(html, &exclusion_selectors(&[], false), ExtractionStrategy::DEFAULT_ORDER)
+    }
+
+    #[test]
+    fn gitbook_cloud_strips_outline_and_pager() {
+        let html = r##"<html><head><meta name="generator" content="GitBook (12.3.0)"></head><body>
+            <main>
+                <h1>Setup</h1>
+                <p>Install the package with your package manager of choice.</p>
+                <aside><p>On this page</p><a href="#install">Install</a></aside>
+                <div class="flex mt-6"><a href="/intro"><span>Previous</span><span>Introduction</span></a><a href="/usage"><span>Next</span><span>Usage</span></a></div>
+            </main>
+        </body></html>"##;
+        let (content, tier) = extract(html);
+        assert_eq!(tier, ExtractionTier::Framework("GitBook (Cloud)"));
+        assert!(content.contains("Install the package"));
+        assert!(!content.contains("On this page"));
+        assert!(!content.contains("Introduction"));
+    }
+
+    #[test]
+    fn absolutize_rewrites_attributes_only() {
+        let html = r#"<p><a href="/guide">Guide</a> <img src="img/a.png"></p><pre><code>&lt;a href="/x"&gt;link&lt;/a&gt;</code></pre>"#;
+        let absolute = absolutize_urls(html, "https://example.com/docs/page");
+        assert!(absolute.contains(r#"href="https://example.com/guide""#));
+        assert!(absolute.contains(r#"src="https://example.com/docs/img/a.png""#));
+        assert!(absolute.contains(r#"&lt;a href="/x"&gt;"#));
+    }
+
+    // (profile, page, text that must survive, chrome that must be stripped)
+    const PROFILE_FIXTURES: &[(&str, &str, &str, &[&str])] = &[
+        (
+            "Astro Starlight",
+            r##"<main data-pagefind-body><div class="sl-markdown-content">
+                <h2>Intro<a class="sl-anchor-link" href="#intro">ANCHOR</a></h2><p>Starlight body.</p>
+                <starlight-toc>TOC ENTRY</starlight-toc><div class="right-sidebar">SIDEBAR</div>
+                <div class="pagination-links"><a href="/next">PAGER</a></div>
+            </div></main>"##,
+            "Starlight body.",
+            &["ANCHOR", "TOC ENTRY", "SIDEBAR", "PAGER"],
+        ),
+        (
+            "Mintlify",
+            r#"<div id="content-area"><div id="navbar">NAVBAR</div><div id="sidebar">SIDEBAR</div>
+                <p>Mintlify body.</p><div id="table-of-contents">TOC ENTRY</div>
+                <div id="pagination">PAGER</div><div id="footer">FOOTER</div></div>"#,
+            "Mintlify body.",
+            &["NAVBAR", "SIDEBAR", "TOC ENTRY", "PAGER", "FOOTER"],
+        ),
+        (
+            "Docsify",
+            r#"<section class="content"><article class="markdown-section"><p>Docsify body.</p>
+                <div class="app-nav">NAVBAR</div><div class="sidebar">SIDEBAR</div>
+                <div class="docsify-pagination-container">PAGER</div></article></section>"#,
+            "Docsify body.",
+            &["NAVBAR", "SIDEBAR", "PAGER"],
+        ),
+        (
+            "VitePress",
+            r##"<div class="VPDoc"><div class="vp-doc"><h2>Intro<a class="header-anchor" href="#intro">ANCHOR</a></h2>
+                <p>VitePress body.</p><div class="VPDocAside">TOC ENTRY</div><div class="VPSidebar">SIDEBAR</div>
+                <div class="VPLocalNav">NAVBAR</div><div class="VPDocFooter">PAGER</div></div></div>"##,
+            "VitePress body.",
+            &["ANCHOR", "TOC ENTRY", "SIDEBAR", "NAVBAR", "PAGER"],
+        ),
+        (
+            "VuePress",
+            r##"<div class="theme-container"><div class="theme-default-content">
+                <h2>Intro<a class="header-anchor" href="#intro">ANCHOR</a></h2><p>VuePress body.</p>
+                <div class="sidebar">SIDEBAR</div><div class="page-meta">META</div><div class="page-nav">PAGER</div>
+            </div></div>"##,
+            "VuePress body.",
+            &["ANCHOR", "SIDEBAR", "META", "PAGER"],
+        ),
+        (
+            "Antora",
+            r##"<main class="article"><div class="toolbar">TOOLBAR</div><article class="doc">
+                <h2>Intro<a class="anchor" href="#intro">ANCHOR</a></h2><p>Antora body.</p>
+                <div class="toc">TOC ENTRY</div><div class="nav-container">SIDEBAR</div>
+                <div class="pagination">PAGER</div></article></main>"##,
+            "Antora body.",
+            &["TOOLBAR", "ANCHOR", "TOC ENTRY", "SIDEBAR", "PAGER"],
+        ),
+        (
+            "Jekyll (Just the Docs)",
+            r##"<div class="main-content-wrap"><div id="main-content"><div class="breadcrumb-nav">NAVBAR</div>
+                <h2>Intro<a class="anchor-heading" href="#intro">ANCHOR</a></h2><p>Just the Docs body.</p>
+                <ul id="markdown-toc"><li>TOC ENTRY</li></ul><div class="side-bar">SIDEBAR</div>
+                <div class="site-footer">FOOTER</div></div></div>"##,
+            "Just the Docs body.",
+            &["NAVBAR", "ANCHOR", "TOC ENTRY", "SIDEBAR", "FOOTER"],
+        ),
+        (
+            "Fumadocs",
+            r#"<div id="nd-page"><div id="nd-subnav">NAVBAR</div><div id="nd-sidebar">SIDEBAR</div>
+                <p>Fumadocs body.</p><div id="nd-toc">TOC ENTRY</div><footer>PAGER</footer></div>"#,
+            "Fumadocs body.",
+            &["NAVBAR", "SIDEBAR", "TOC ENTRY", "PAGER"],
+        ),
+    ];
+
+    #[test]
+    fn generator_profiles_match_and_strip_chrome() {
+        for &(profile, page, body, chrome) in PROFILE_FIXTURES {
+            let (content, tier) = extract(&format!("<html><body>{}</body></html>", page));
+            assert_eq!(tier, ExtractionTier::Framework(profile), "{} fixture", profile);
+            assert!(content.contains(body), "{} lost its content", profile);
+            for noise in chrome {
+                assert!(!content.contains(noise), "{} kept {}", profile, noise);
+            }
+        }
+    }
+
+    #[test]
+    fn nested_content_matches_are_emitted_once() {
+        let html = r#"<html><body><div class="page-inner">
+            <section><p>Outer section text.</p><section><p>Nested section text.</p></section></section>
+        </div></body></html>"#;
+        let (content, tier) = extract(html);
+        assert_eq!(tier, ExtractionTier::Framework("GitBook (Legacy)"));
+        assert_eq!(content.matches("Nested section text.").count(), 1);
+        assert_eq!(content.matches("Outer section text.").count(), 1);
+
+        let html = r#"<html><body><div class="md-main"><div class="md-content__inner">
+            <p>Material page.</p><div class="md-content__inner"><p>Inner block.</p></div>
+        </div></div></body></html>"#;
+        let (content, tier) = extract(html);
+        assert_eq!(tier, ExtractionTier::Framework("MkDocs (Material)"));
+        assert_eq!(content.matches("Inner block.").count(), 1);
+    }
+
+    // The shape load_js_script returns: no doctype, attributes kept on <html>, scripts dropped
+    const COMPOSED_PAGE: &str = r#"<html lang="en" class="dark"><head><title>Page Title Text</title><meta name="description" content="x"></head><body><h1>Heading</h1><p>Body paragraph.</p></body></html>"#;
+
+    #[test]
+    fn document_wrapper_is_reduced_to_body() {
+        let body = strip_document_wrapper(COMPOSED_PAGE);
+        assert!(!body.contains("Page Title Text"));
+        assert!(body.contains("<p>Body paragraph.</p>"));
+        assert_eq!(strip_document_wrapper("<p>Fragment</p>"), "<p>Fragment</p>");
+    }
+
+    #[test]
+    fn full_page_and_raw_output_skip_head() {
+        // Raw mode converts the composed page as is
+        for converter in MarkdownConverter::ALL {
+            let markdown = to_markdown(COMPOSED_PAGE, *converter);
+            assert!(!markdown.contains("Page Title Text"), "{:?} leaked the title", converter);
+            assert!(markdown.contains("Body paragraph."));
+        }
+
+        // No tier configured: the full-page fallback
+        let (content, tier) = extract_content(COMPOSED_PAGE, &[], &[]);
+        assert_eq!(tier, ExtractionTier::FullPage);
+        let markdown = to_markdown(&content, MarkdownConverter::default());
+        assert!(!markdown.contains("Page Title Text"));
+    }
+
+    #[test]
+    fn images_in_table_cells_keep_their_alt_text() {
+        let html = r#"<table><tr><th>Icon</th><th>Name</th></tr>
+            <tr><td><img src="https://a.dev/ok.png" alt="Supported"></td><td>Linux</td></tr>
+            <tr><td><img src="https://a.dev/x.png" title="Not | yet"></td><td>BSD</td></tr></table>"#;
+        for converter in MarkdownConverter::ALL {
+            let markdown = to_markdown(html, *converter);
+            assert!(markdown.contains("| ![Supported](https://a.dev/ok.png) | Linux |"), "{:?}: {}", converter, markdown);
+            assert!(markdown.contains("| ![Not \\| yet](https://a.dev/x.png) | BSD |"), "{:?}: {}", converter, markdown);
+        }
+    }
+
+    #[test]
+    fn bare_main_is_not_gitbook() {
+        let html = r#"<html><body><main><h1>Guide</h1><p>Plain site content.</p>
+            <div><a href="/next">Next steps</a></div></main></body></html>"#;
+        let (_, tier) = extract(html);
+        assert_ne!(tier, ExtractionTier::Framework("GitBook (Cloud)"));
+    }
+}
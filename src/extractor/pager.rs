@@ -0,0 +1,94 @@
+use scraper::{ElementRef, Html, Node, Selector};
+
+// Labels a pager link opens with, lowercase
+const PAGER_LABELS: &[&str] = &["previous", "next"];
+
+// Containers a pager is wrapped in
+const PAGER_CONTAINERS: &str = "div, nav, footer";
+
+// Removes "Previous / Next page" blocks from extracted HTML, for frameworks whose pager has no
+// stable class to exclude: containers holding one or two links and no text outside them, where
+// a link is labelled exactly Previous or Next, optionally followed by the target page's title in
+// its own element.
+pub fn strip_pagers(html: &str) -> String {
+    let mut fragment = Html::parse_fragment(html);
+    let Ok(containers) = Selector::parse(PAGER_CONTAINERS) else {
+        return html.to_string();
+    };
+
+    // Outermost pagers only; detaching them takes any nested match along
+    let mut pagers = Vec::new();
+    for container in fragment.select(&containers) {
+        if is_pager(container) && !container.ancestors().any(|ancestor| pagers.contains(&ancestor.id())) {
+            pagers.push(container.id());
+        }
+    }
+    if pagers.is_empty() {
+        return html.to_string();
+    }
+    for id in pagers {
+        if let Some(mut node) = fragment.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    fragment.root_element().inner_html()
+}
+
+fn is_pager(container: ElementRef) -> bool {
+    let Ok(links) = Selector::parse("a[href]") else {
+        return false;
+    };
+    let links: Vec<ElementRef> = container.select(&links).collect();
+    if links.is_empty() || links.len() > 2 {
+        return false;
+    }
+    let text_outside_links = container.descendants().any(|node| {
+        matches!(node.value(), Node::Text(text) if !text.trim().is_empty())
+            && !node.ancestors().filter_map(ElementRef::wrap).any(|ancestor| ancestor.value().name() == "a")
+    });
+    !text_outside_links && links.iter().any(|link| is_pager_link(*link))
+}
+
+// The link's first text node, arrows aside, must be the label on its own: "Previous" or
+// "Next ›" qualify, "Next.js guide" and "Next steps" don't
+fn is_pager_link(link: ElementRef) -> bool {
+    let Some(label) = link.text().map(str::trim).find(|text| !text.is_empty()) else {
+        return false;
+    };
+    let label = label.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+    PAGER_LABELS.contains(&label.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_gitbook_pager_cards() {
+        let html = r#"<p>Body text.</p><div class="flex mt-6"><a href="/intro"><span>Previous</span><span>Introduction</span></a><a href="/setup"><span>Next</span><span>Setup</span></a></div>"#;
+        let stripped = strip_pagers(html);
+        assert!(stripped.contains("Body text."));
+        assert!(!stripped.contains("Introduction"));
+        assert!(!stripped.contains("Setup"));
+    }
+
+    #[test]
+    fn removes_arrow_labelled_pager() {
+        let stripped = strip_pagers(r#"<p>Body</p><nav><a href="/b">Next ›</a></nav>"#);
+        assert!(!stripped.contains("Next"));
+    }
+
+    #[test]
+    fn keeps_links_that_only_start_with_next() {
+        let html = r#"<div><a href="/nextjs">Next.js guide</a></div><div><a href="/steps">Next steps</a></div>"#;
+        let stripped = strip_pagers(html);
+        assert!(stripped.contains("Next.js guide"));
+        assert!(stripped.contains("Next steps"));
+    }
+
+    #[test]
+    fn keeps_pager_like_links_with_surrounding_text() {
+        let stripped = strip_pagers(r#"<div>Continue to the <a href="/b">Next</a> chapter.</div>"#);
+        assert!(stripped.contains("chapter"));
+    }
+}
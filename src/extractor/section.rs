@@ -0,0 +1,93 @@
+use scraper::{ElementRef, Html, Node};
+
+// Containers whose id names the section they wrap, as Sphinx and many static generators emit
+const SECTION_CONTAINERS: &[&str] = &["section", "article", "div"];
+
+// The part of extracted HTML a URL fragment points at: the element with that id (or an <a name>)
+// and, when it is a heading or an anchor at a heading, everything after it up to the next heading
+// of the same or a higher level. A container carrying the id is returned whole. None when the
+// page has no such anchor.
+pub fn section_for_anchor(html: &str, anchor: &str) -> Option<String> {
+    let fragment = Html::parse_fragment(html);
+    let target = fragment.root_element().descendants().filter_map(ElementRef::wrap).find(|element| {
+        let value = element.value();
+        value.id() == Some(anchor) || (value.name() == "a" && value.attr("name") == Some(anchor))
+    })?;
+
+    let heading = if heading_level(target).is_some() {
+        Some(target)
+    } else if let Some(heading) = target.ancestors().filter_map(ElementRef::wrap).find(|e| heading_level(*e).is_some()) {
+        // <h2><a id="installation"></a>Installation</h2>
+        Some(heading)
+    } else if SECTION_CONTAINERS.contains(&target.value().name()) {
+        return Some(target.html());
+    } else {
+        // An empty <a id> placed right before its heading, as older generators do
+        target.next_siblings().filter_map(ElementRef::wrap).next().filter(|e| heading_level(*e).is_some())
+    };
+
+    let (start, level) = match heading {
+        Some(heading) => (heading, heading_level(heading)?),
+        // A bare anchor in running text: everything up to the next heading of any level
+        None => (target, 6),
+    };
+    let start = outermost_wrapper(start);
+
+    let mut section = start.html();
+    for sibling in start.next_siblings() {
+        match sibling.value() {
+            Node::Element(_) => {
+                let element = ElementRef::wrap(sibling)?;
+                if starts_section(element, level) {
+                    break;
+                }
+                section.push_str(&element.html());
+            }
+            Node::Text(text) => section.push_str(&escape_text(text)),
+            _ => {}
+        }
+    }
+    Some(section)
+}
+
+fn heading_level(element: ElementRef) -> Option<u8> {
+    match element.value().name() {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+// Whether the element is, or wraps, a heading that ends a section of the given level
+fn starts_section(element: ElementRef, level: u8) -> bool {
+    element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter_map(heading_level)
+        .any(|found| found <= level)
+}
+
+// Climbs out of wrappers that hold nothing but the element (e.g. <div class="heading-wrapper">),
+// so its siblings are the content that follows it
+fn outermost_wrapper(element: ElementRef) -> ElementRef {
+    let mut current = element;
+    while let Some(parent) = current.parent().and_then(ElementRef::wrap) {
+        let only_child = parent.children().filter_map(ElementRef::wrap).count() == 1;
+        let has_text = parent
+            .children()
+            .any(|child| matches!(child.value(), Node::Text(text) if !text.trim().is_empty()));
+        if !only_child || has_text || parent.parent().and_then(ElementRef::wrap).is_none() {
+            break;
+        }
+        current = parent;
+    }
+    current
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
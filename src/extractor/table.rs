@@ -0,0 +1,121 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use scraper::{ElementRef, Html, Selector};
+use super::MarkdownConverter;
+use super::dom::belongs_to;
+use super::image::Images;
+
+lazy_static! {
+    static ref TABLE_TAG: Regex = Regex::new(r"(?i)<table\b|</table\s*>").unwrap();
+    static ref PLACEHOLDER: Regex = Regex::new(r"DOCSERTABLE(\d+)END").unwrap();
+}
+
+// Tables converted to GFM pipe tables up front and spliced back in after markdown conversion,
+// since the converters flatten reference tables into runs of text
+pub struct Tables {
+    tables: Vec<String>,
+}
+
+impl Tables {
+    pub fn extract(html: &str, converter: MarkdownConverter) -> (String, Self) {
+        let mut tables = Vec::new();
+        let mut output = String::with_capacity(html.len());
+        let mut copied_up_to = 0;
+        let mut depth = 0;
+        let mut table_start = 0;
+
+        // Only outermost tables are converted; nested ones end up flattened into their cell
+        for tag in TABLE_TAG.find_iter(html) {
+            if tag.as_str().starts_with("</") {
+                if depth == 0 {
+                    continue;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    let table_html = &html[table_start..tag.end()];
+                    output.push_str(&html[copied_up_to..table_start]);
+                    match to_pipe_table(table_html, converter) {
+                        Some(table) => {
+                            output.push_str(&format!("<p>DOCSERTABLE{}END</p>", tables.len()));
+                            tables.push(table);
+                        }
+                        None => output.push_str(table_html),
+                    }
+                    copied_up_to = tag.end();
+                }
+            } else {
+                if depth == 0 {
+                    table_start = tag.start();
+                }
+                depth += 1;
+            }
+        }
+        output.push_str(&html[copied_up_to..]);
+        (output, Self { tables })
+    }
+
+    pub fn restore(&self, markdown: &str) -> String {
+        if self.tables.is_empty() {
+            return markdown.to_string();
+        }
+        PLACEHOLDER
+            .replace_all(markdown, |caps: &Captures| {
+                match caps[1].parse::<usize>().ok().and_then(|index| self.tables.get(index)) {
+                    Some(table) => table.clone(),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+fn to_pipe_table(table_html: &str, converter: MarkdownConverter) -> Option<String> {
+    let fragment = Html::parse_fragment(table_html);
+    let table_selector = Selector::parse("table").ok()?;
+    let row_selector = Selector::parse("tr").ok()?;
+    let table = fragment.select(&table_selector).next()?;
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for row in table.select(&row_selector) {
+        if !belongs_to(row, table) {
+            continue;
+        }
+        let mut cells = Vec::new();
+        for cell in row.children().filter_map(ElementRef::wrap) {
+            if !matches!(cell.value().name(), "td" | "th") {
+                continue;
+            }
+            cells.push(cell_markdown(&cell, converter));
+            // Spanned columns are flattened into empty cells so the columns still line up
+            let colspan: usize = cell.value().attr("colspan").and_then(|span| span.trim().parse().ok()).unwrap_or(1);
+            cells.extend(std::iter::repeat_n(String::new(), colspan.clamp(1, 50) - 1));
+        }
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+
+    let columns = rows.iter().map(Vec::len).max()?;
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, mut row) in rows.into_iter().enumerate() {
+        row.resize(columns, String::new());
+        lines.push(format!("| {} |", row.join(" | ")));
+        // GFM requires a header row; the first row serves as one even without <thead>/<th>
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+// Renders a cell's contents as single-line inline markdown, keeping links, emphasis, code and
+// images (with their alt text, as in the rest of the page)
+fn cell_markdown(cell: &ElementRef, converter: MarkdownConverter) -> String {
+    let (html, images) = Images::extract(&cell.inner_html());
+    images
+        .restore(&converter.convert(&html))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
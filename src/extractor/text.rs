@@ -0,0 +1,78 @@
+use scraper::{ElementRef, Html, Node};
+use super::dom::{BLOCK_ELEMENTS, SKIPPED_ELEMENTS};
+
+// Converts extracted HTML to readable plain text: one paragraph per block element, whitespace
+// collapsed except inside <pre>, code kept verbatim without fences, table cells separated by tabs
+pub fn to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut out = String::new();
+    write_text(fragment.root_element(), false, &mut out);
+
+    // Paragraph breaks are pushed liberally; squeeze them to single blank lines
+    let mut text = String::with_capacity(out.len());
+    let mut blank_lines = 0;
+    for line in out.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !text.is_empty() {
+            text.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        text.push_str(line);
+        blank_lines = 0;
+    }
+    text
+}
+
+fn write_text(element: ElementRef, preformatted: bool, out: &mut String) {
+    let name = element.value().name();
+    if SKIPPED_ELEMENTS.contains(&name) {
+        return;
+    }
+    let preformatted = preformatted || name == "pre";
+    let block = BLOCK_ELEMENTS.contains(&name);
+    if block {
+        out.push_str("\n\n");
+    }
+
+    for child in element.children() {
+        match child.value() {
+            Node::Element(_) => {
+                if let Some(child_element) = ElementRef::wrap(child) {
+                    write_text(child_element, preformatted, out);
+                }
+            }
+            Node::Text(text) if preformatted => out.push_str(text),
+            Node::Text(text) => push_collapsed(text, out),
+            _ => {}
+        }
+    }
+
+    match name {
+        "br" => out.push('\n'),
+        "td" | "th" => out.push('\t'),
+        "img" => {
+            if let Some(alt) = element.value().attr("alt").filter(|alt| !alt.trim().is_empty()) {
+                push_collapsed(alt, out);
+            }
+        }
+        _ if block => out.push_str("\n\n"),
+        _ => {}
+    }
+}
+
+// Appends text with runs of whitespace folded into one space, never starting a line with a space
+fn push_collapsed(text: &str, out: &mut String) {
+    let mut pending_space = text.starts_with(char::is_whitespace);
+    for word in text.split_whitespace() {
+        if pending_space && !out.is_empty() && !out.ends_with(char::is_whitespace) {
+            out.push(' ');
+        }
+        out.push_str(word);
+        pending_space = true;
+    }
+    if text.ends_with(char::is_whitespace) && !out.is_empty() && !out.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+}
@@ -0,0 +1,72 @@
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use crate::models::TocEntry;
+
+// Nested outline of the h1-h6 headings in extracted HTML. Anchors are the headings' own ids when
+// they have one, otherwise GitHub-style slugs of their text (deduplicated with -1, -2...).
+pub fn table_of_contents(html: &str) -> Vec<TocEntry> {
+    let fragment = Html::parse_fragment(html);
+    let Ok(selector) = Selector::parse("h1, h2, h3, h4, h5, h6") else {
+        return Vec::new();
+    };
+
+    let mut used_slugs: HashMap<String, usize> = HashMap::new();
+    let mut headings = Vec::new();
+    for heading in fragment.select(&selector) {
+        let text = heading.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        let level = heading.value().name()[1..].parse().unwrap_or(1);
+        let anchor = match heading.value().id() {
+            Some(id) if !id.is_empty() => id.to_string(),
+            _ => unique_slug(&slugify(&text), &mut used_slugs),
+        };
+        headings.push(TocEntry { level, text, anchor, children: Vec::new() });
+    }
+    nest(headings)
+}
+
+// Turns a flat heading list into a tree: each heading owns the following deeper headings
+fn nest(headings: Vec<TocEntry>) -> Vec<TocEntry> {
+    let mut roots: Vec<TocEntry> = Vec::new();
+    // Open ancestors of the next heading, outermost first
+    let mut stack: Vec<TocEntry> = Vec::new();
+    for heading in headings {
+        while stack.last().is_some_and(|open| open.level >= heading.level) {
+            close_last(&mut stack, &mut roots);
+        }
+        stack.push(heading);
+    }
+    while !stack.is_empty() {
+        close_last(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close_last(stack: &mut Vec<TocEntry>, roots: &mut Vec<TocEntry>) {
+    if let Some(entry) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => roots.push(entry),
+        }
+    }
+}
+
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '_' => Some(c),
+            ' ' | '-' => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn unique_slug(slug: &str, used: &mut HashMap<String, usize>) -> String {
+    let count = used.entry(slug.to_string()).or_insert(0);
+    let unique = if *count == 0 { slug.to_string() } else { format!("{}-{}", slug, count) };
+    *count += 1;
+    unique
+}
@@ -0,0 +1,150 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
+
+// How a documentation site puts the language in its URLs
+enum LangScheme {
+    // A locale path segment right after the host, e.g. /en-US/docs/...; English maps to `english`
+    PathLocale { pattern: &'static Regex, english: &'static str },
+    // An hl query parameter, e.g. ?hl=fr
+    QueryParam(&'static str),
+    // A language segment before the version, e.g. /fr/3/library/...; English has none
+    PathBeforeVersion(&'static Regex),
+}
+
+struct LocalizedSite {
+    host: &'static str,
+    scheme: LangScheme,
+}
+
+lazy_static! {
+    static ref MDN_LOCALE: Regex = Regex::new(r"^/([a-zA-Z]{2}(?:-[a-zA-Z]{2})?)(/|$)").unwrap();
+    static ref PYTHON_VERSION: Regex = Regex::new(r"^/(?:[a-z]{2}(?:-[a-z]{2})?/)?(\d[\w.]*|dev)(/|$)").unwrap();
+    static ref LOCALIZED_SITES: Vec<LocalizedSite> = vec![
+        LocalizedSite {
+            host: "developer.mozilla.org",
+            scheme: LangScheme::PathLocale { pattern: &MDN_LOCALE, english: "en-US" },
+        },
+        LocalizedSite {
+            host: "developer.android.com",
+            scheme: LangScheme::QueryParam("hl"),
+        },
+        LocalizedSite {
+            host: "docs.python.org",
+            scheme: LangScheme::PathBeforeVersion(&PYTHON_VERSION),
+        },
+    ];
+}
+
+// The URL of the same page in another language on sites with path- or parameter-based i18n.
+// lang is a BCP 47 tag such as "fr" or "pt-BR". None for sites docser doesn't know, where the
+// Accept-Language header is all there is.
+pub fn localize_url(raw: &str, lang: &str) -> Option<String> {
+    let mut url = Url::parse(raw).ok()?;
+    let site = LOCALIZED_SITES.iter().find(|site| url.host_str() == Some(site.host))?;
+    let (language, region) = split_tag(lang)?;
+
+    match &site.scheme {
+        LangScheme::PathLocale { pattern, english } => {
+            let locale = match (language.as_str(), region) {
+                ("en", None) => english.to_string(),
+                (language, Some(region)) => format!("{}-{}", language, region.to_uppercase()),
+                (language, None) => language.to_string(),
+            };
+            let path = url.path().to_string();
+            let captures = pattern.captures(&path)?;
+            let rest = &path[captures.get(1)?.end()..];
+            url.set_path(&format!("/{}{}", locale, rest));
+        }
+        LangScheme::QueryParam(name) => {
+            let value = match region {
+                Some(region) => format!("{}-{}", language, region.to_lowercase()),
+                None => language.clone(),
+            };
+            let pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| key.as_ref() != *name)
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            url.query_pairs_mut().clear().extend_pairs(pairs).append_pair(name, &value);
+        }
+        LangScheme::PathBeforeVersion(pattern) => {
+            let path = url.path().to_string();
+            let captures = pattern.captures(&path)?;
+            let version_start = captures.get(1)?.start();
+            let prefix = match (language.as_str(), region) {
+                ("en", _) => String::new(),
+                (language, Some(region)) => format!("/{}-{}", language, region.to_lowercase()),
+                (language, None) => format!("/{}", language),
+            };
+            url.set_path(&format!("{}/{}", prefix, &path[version_start..]));
+        }
+    }
+    Some(url.to_string())
+}
+
+// "pt-BR" into ("pt", Some("BR")); None for anything that isn't a language tag
+fn split_tag(lang: &str) -> Option<(String, Option<&str>)> {
+    let mut parts = lang.trim().split(['-', '_']);
+    let language = parts.next()?.to_ascii_lowercase();
+    let region = parts.next().filter(|region| region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()));
+    let valid = (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic());
+    valid.then_some((language, region))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mdn_swaps_the_locale_segment() {
+        let url = "https://developer.mozilla.org/en-US/docs/Web/API/fetch";
+        assert_eq!(localize_url(url, "fr").as_deref(), Some("https://developer.mozilla.org/fr/docs/Web/API/fetch"));
+        assert_eq!(localize_url(url, "pt-br").as_deref(), Some("https://developer.mozilla.org/pt-BR/docs/Web/API/fetch"));
+        assert_eq!(
+            localize_url("https://developer.mozilla.org/fr/docs/Web/API/fetch", "en").as_deref(),
+            Some("https://developer.mozilla.org/en-US/docs/Web/API/fetch")
+        );
+    }
+
+    #[test]
+    fn android_sets_a_single_hl_parameter() {
+        assert_eq!(
+            localize_url("https://developer.android.com/guide", "fr").as_deref(),
+            Some("https://developer.android.com/guide?hl=fr")
+        );
+        assert_eq!(
+            localize_url("https://developer.android.com/guide?hl=en&tab=kotlin", "zh-CN").as_deref(),
+            Some("https://developer.android.com/guide?tab=kotlin&hl=zh-cn")
+        );
+    }
+
+    #[test]
+    fn python_puts_the_language_before_the_version() {
+        let url = "https://docs.python.org/3/library/os.html";
+        assert_eq!(localize_url(url, "fr").as_deref(), Some("https://docs.python.org/fr/3/library/os.html"));
+        assert_eq!(localize_url(url, "pt-BR").as_deref(), Some("https://docs.python.org/pt-br/3/library/os.html"));
+        assert_eq!(
+            localize_url("https://docs.python.org/ja/3/library/os.html", "en").as_deref(),
+            Some("https://docs.python.org/3/library/os.html")
+        );
+    }
+
+    #[test]
+    fn already_localized_urls_are_not_prefixed_twice() {
+        assert_eq!(
+            localize_url("https://docs.python.org/fr/3/library/os.html", "fr").as_deref(),
+            Some("https://docs.python.org/fr/3/library/os.html")
+        );
+        assert_eq!(
+            localize_url("https://developer.mozilla.org/fr/docs/Web", "fr").as_deref(),
+            Some("https://developer.mozilla.org/fr/docs/Web")
+        );
+    }
+
+    #[test]
+    fn unsupported_hosts_and_tags_are_none() {
+        assert_eq!(localize_url("https://docs.rs/serde", "fr"), None);
+        assert_eq!(localize_url("https://docs.python.org/3/", "french"), None);
+    }
+}
@@ -1,18 +1,79 @@
+mod cache;
+mod config;
 mod constants;
+mod devices;
+mod error;
+mod i18n;
+mod metrics;
 mod models;
+mod ratelimit;
 mod browser;
+mod robots;
+mod search;
 mod server;
+mod sitemap;
+mod urls;
 pub mod extractor;
 
 use server::SimpleServer;
 use rmcp::{ServiceExt, transport::stdio};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let server = SimpleServer::new().await;
+    init_logging();
+
+    let server = SimpleServer::try_new().await?;
+    let handle = server.clone();
 
     let service = server.serve(stdio()).await?;
 
-    service.waiting().await?;
+    // Serve until the client disconnects or we're told to stop, then tear the browser down
+    let result = tokio::select! {
+        result = service.waiting() => result.map(|_| ()),
+        _ = shutdown_signal() => {
+            info!("Shutdown signal received");
+            Ok(())
+        }
+    };
+    handle.shutdown().await;
+    result?;
     Ok(())
 }
+
+// Logs go to stderr since stdout carries the MCP protocol. RUST_LOG controls verbosity
+// (default docser=info) and DOCSER_LOG_FORMAT=json switches to one JSON object per line.
+fn init_logging() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("docser=info"));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    if std::env::var("DOCSER_LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
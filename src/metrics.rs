@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::error::DocserError;
+use crate::models::{DurationBucket, Stats};
+
+// Upper bounds of the scrape duration histogram buckets, in milliseconds
+const DURATION_BUCKETS_MS: &[u64] = &[250, 500, 1000, 2500, 5000, 10_000, 30_000];
+
+// Process-wide counters, updated lock-free except for the per-kind failure map
+pub struct Metrics {
+    started: Instant,
+    scrapes: AtomicU64,
+    scrape_failures: AtomicU64,
+    page_cache_hits: AtomicU64,
+    // One count per navigation-to-markdown attempt, so retries show up as extra samples
+    scrape_attempts: AtomicU64,
+    scrape_time_ms: AtomicU64,
+    scrape_time_max_ms: AtomicU64,
+    // One counter per DURATION_BUCKETS_MS entry plus the overflow bucket
+    duration_buckets: Vec<AtomicU64>,
+    searches: AtomicU64,
+    search_failures: AtomicU64,
+    search_cache_hits: AtomicU64,
+    failures_by_kind: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            scrapes: AtomicU64::new(0),
+            scrape_failures: AtomicU64::new(0),
+            page_cache_hits: AtomicU64::new(0),
+            scrape_attempts: AtomicU64::new(0),
+            scrape_time_ms: AtomicU64::new(0),
+            scrape_time_max_ms: AtomicU64::new(0),
+            duration_buckets: (0..=DURATION_BUCKETS_MS.len()).map(|_| AtomicU64::new(0)).collect(),
+            searches: AtomicU64::new(0),
+            search_failures: AtomicU64::new(0),
+            search_cache_hits: AtomicU64::new(0),
+            failures_by_kind: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record_scrape<T>(&self, result: &Result<T, DocserError>) {
+        self.scrapes.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = result {
+            self.scrape_failures.fetch_add(1, Ordering::Relaxed);
+            self.record_failure(e);
+        }
+    }
+
+    pub fn record_page_cache_hit(&self) {
+        self.scrapes.fetch_add(1, Ordering::Relaxed);
+        self.page_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_scrape_attempt(&self, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        self.scrape_attempts.fetch_add(1, Ordering::Relaxed);
+        self.scrape_time_ms.fetch_add(ms, Ordering::Relaxed);
+        self.scrape_time_max_ms.fetch_max(ms, Ordering::Relaxed);
+        let bucket = DURATION_BUCKETS_MS
+            .iter()
+            .position(|upper| ms <= *upper)
+            .unwrap_or(DURATION_BUCKETS_MS.len());
+        self.duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_search<T>(&self, result: &Result<T, DocserError>) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = result {
+            self.search_failures.fetch_add(1, Ordering::Relaxed);
+            self.record_failure(e);
+        }
+    }
+
+    pub fn record_search_cache_hit(&self) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        self.search_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_failure(&self, error: &DocserError) {
+        let mut failures = self.failures_by_kind.lock().unwrap_or_else(|e| e.into_inner());
+        *failures.entry(error.kind()).or_insert(0) += 1;
+    }
+
+    pub fn snapshot(&self) -> Stats {
+        let attempts = self.scrape_attempts.load(Ordering::Relaxed);
+        let total_ms = self.scrape_time_ms.load(Ordering::Relaxed);
+        let scrape_duration_histogram = self
+            .duration_buckets
+            .iter()
+            .enumerate()
+            .map(|(index, count)| DurationBucket {
+                le_ms: DURATION_BUCKETS_MS.get(index).copied(),
+                count: count.load(Ordering::Relaxed),
+            })
+            .collect();
+        Stats {
+            uptime_secs: self.started.elapsed().as_secs(),
+            scrapes: self.scrapes.load(Ordering::Relaxed),
+            scrape_failures: self.scrape_failures.load(Ordering::Relaxed),
+            page_cache_hits: self.page_cache_hits.load(Ordering::Relaxed),
+            scrape_attempts: attempts,
+            avg_scrape_ms: if attempts == 0 { 0 } else { total_ms / attempts },
+            max_scrape_ms: self.scrape_time_max_ms.load(Ordering::Relaxed),
+            scrape_duration_histogram,
+            searches: self.searches.load(Ordering::Relaxed),
+            search_failures: self.search_failures.load(Ordering::Relaxed),
+            search_cache_hits: self.search_cache_hits.load(Ordering::Relaxed),
+            failures_by_kind: self
+                .failures_by_kind
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|(kind, count)| (kind.to_string(), *count))
+                .collect(),
+        }
+    }
+}
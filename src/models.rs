@@ -1,24 +1,677 @@
 use rmcp::schemars;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CrawlUrlRequest {
     pub url: String,
+    /// Return a JSON object with the page title, description, canonical URL and HTTP response
+    /// (status, final URL, headers) alongside the markdown
+    #[serde(default)]
+    pub include_metadata: bool,
+    /// Truncate the markdown (or the JSON blocks' text) to this many characters, marking the cut with "[truncated]" (default: unlimited)
+    pub max_chars: Option<usize>,
+    /// Also return the page's heading outline as a nested JSON table of contents
+    #[serde(default)]
+    pub include_toc: bool,
+    /// "markdown" (default), "text" for plain prose without markdown syntax, e.g. for embeddings, or
+    /// "json" for an array of typed blocks (heading, paragraph, code, list, table, quote). In text
+    /// mode code keeps its content without fences; with include_metadata the text is in the markdown
+    /// field and the blocks in a blocks field
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Also return the extracted HTML the markdown was converted from (main content only, cleaned,
+    /// with absolute URLs), as a JSON object {markdown, html}, for running other converters over it
+    #[serde(default)]
+    pub include_html: bool,
+    /// What happens to link URLs in the markdown: "keep" (default) leaves links inline, "text_only"
+    /// keeps only the link text, e.g. for embeddings, and "footnotes" turns them into reference
+    /// links with the URLs listed at the end (after any max_chars cut, which they don't count towards)
+    #[serde(default)]
+    pub links: LinkStyle,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStyle {
+    #[default]
+    Keep,
+    TextOnly,
+    Footnotes,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Markdown,
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    pub const ALL: &[OutputFormat] = &[OutputFormat::Markdown, OutputFormat::Text, OutputFormat::Json];
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CrawlUrlsRequest {
+    pub urls: Vec<String>,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CrawlSiteRequest {
+    /// Page to start crawling from; only links on the same host are followed
+    pub url: String,
+    /// How many links away from the start page to follow (default 2)
+    pub max_depth: Option<u32>,
+    /// Maximum number of pages to crawl in total (default 20)
+    pub max_pages: Option<usize>,
+    /// Only follow links whose URL path matches one of these globs, where * matches anything,
+    /// e.g. ["/guide/*"]
+    pub include_patterns: Option<Vec<String>>,
+    /// Never follow links whose URL path matches one of these globs, e.g. ["/blog/*", "*/changelog*"];
+    /// takes precedence over include_patterns
+    pub exclude_patterns: Option<Vec<String>>,
+    /// Return the pages as one markdown document instead of a JSON list: a table of contents, then
+    /// each page under its title and source URL with its headings nested below, in the order the
+    /// start page links to them (crawl order for the rest)
+    #[serde(default)]
+    pub combined: bool,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CrawlSitemapRequest {
+    /// URL of the sitemap or sitemap index, e.g. "https://docs.example.com/sitemap.xml"
+    pub url: String,
+    /// Only crawl pages whose URL starts with this, e.g. "https://docs.example.com/guide/"
+    pub url_prefix: Option<String>,
+    /// Only crawl pages whose URL matches this glob, where * matches anything, e.g. "*/api/*"
+    pub url_pattern: Option<String>,
+    /// Maximum number of pages to crawl (default 50)
+    pub max_pages: Option<usize>,
+    /// Re-crawl every page, even those whose <lastmod> hasn't advanced since the last sitemap crawl
+    #[serde(default)]
+    pub force: bool,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExtractLinksRequest {
+    pub url: String,
+    /// Only return links on the same host as the page
+    #[serde(default)]
+    pub same_domain: bool,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FetchHtmlRequest {
+    pub url: String,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DetectFrameworkRequest {
+    pub url: String,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExtractHtmlRequest {
+    /// Full HTML of a page, e.g. saved from the browser's dev tools
+    pub html: String,
+    /// URL the HTML came from, used to make links and images absolute
+    pub url: Option<String>,
+    /// Extra CSS selectors to strip from the extracted content
+    pub extra_exclusions: Option<Vec<String>>,
+    /// Keep the chrome that is stripped by default
+    #[serde(default)]
+    pub disable_default_exclusions: bool,
+    /// Extraction tiers to try, in order (see crawl_url)
+    pub strategy: Option<Vec<ExtractionStrategy>>,
+}
+
+// crawl_url's response with include_html (and without include_metadata)
+#[derive(Debug, Serialize)]
+pub struct PageContent {
+    pub markdown: String,
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<ContentBlock>>,
+}
+
+// Structured content of every crawl_url response, described by the tool's output schema
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CrawlResult {
+    /// The URL that was crawled
+    pub url: String,
+    /// The page's <title>, or the article title the readability tier found when there is none
+    pub title: Option<String>,
+    /// Author line, found by the readability tier only
+    pub byline: Option<String>,
+    /// Short summary of the article, found by the readability tier only
+    pub excerpt: Option<String>,
+    /// The extracted content in the requested output_format (markdown unless "text" was asked for),
+    /// after max_chars truncation
+    pub markdown: String,
+    /// Whitespace-separated words in markdown
+    pub word_count: usize,
+    /// Rough size of markdown in LLM tokens (about four characters each); tokenizers differ, so
+    /// treat it as an estimate for budgeting
+    pub approx_tokens: usize,
+}
+
+// Result of the extract_html tool
+#[derive(Debug, Serialize)]
+pub struct ExtractedContent {
+    pub markdown: String,
+    /// How the content was isolated, e.g. "framework:Docusaurus v2/v3" or "readability"
+    pub extraction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reader: Option<ReaderMetadata>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RenderPdfRequest {
+    pub url: String,
+    /// Paper size: "A4" (default) or "Letter"
+    #[serde(default)]
+    pub format: PaperFormat,
+    /// Print background colors and images
+    #[serde(default)]
+    pub print_background: bool,
+    #[serde(flatten)]
+    pub options: ScrapeOptions,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, schemars::JsonSchema)]
+pub enum PaperFormat {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PaperFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaperFormat::A4 => "A4",
+            PaperFormat::Letter => "Letter",
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ScrapeOptions {
+    /// CSS selector of the part of the page to convert, e.g. "#method-foo"; every match is kept as-is.
+    /// Falls back to normal extraction when nothing matches
+    pub selector: Option<String>,
+    /// Open collapsed <details> and click aria-expanded="false" accordion toggles before extracting,
+    /// for FAQ and API pages that only render a section's content when it is opened
+    #[serde(default)]
+    pub expand_collapsed: bool,
+    /// Include the content of same-origin iframes (embedded examples, API consoles) where the frame
+    /// sits; cross-origin frames are always skipped
+    #[serde(default)]
+    pub inline_frames: bool,
+    /// Serialize elements' light DOM children instead of their open shadow roots, for sites where
+    /// shadow expansion duplicates content or pulls in framework internals
+    #[serde(default)]
+    pub disable_shadow_dom: bool,
+    /// JavaScript run in every document of the page before the site's own scripts, e.g.
+    /// "localStorage.setItem('cookie-consent', 'dismissed')" to get past a consent wall. It runs
+    /// with the page's full privileges, in third-party frames too, so only pass scripts you trust
+    pub init_script: Option<String>,
+    /// Scroll to the bottom up to this many times before extracting, for infinite-scroll and
+    /// lazy-loaded lists (changelogs, blog indexes); stops early once the page stops growing
+    pub scroll_steps: Option<u32>,
+    /// Pause after each scroll for new content to load, in milliseconds (default 500)
+    pub scroll_delay_ms: Option<u64>,
+    /// Extra CSS selectors to strip from the extracted content, e.g. ".feedback-widget"; invalid
+    /// selectors are skipped
+    pub extra_exclusions: Option<Vec<String>>,
+    /// Keep headers, footers, asides, timestamps and other chrome that is stripped by default.
+    /// Framework-specific exclusions still apply
+    #[serde(default)]
+    pub disable_default_exclusions: bool,
+    /// Extraction tiers to try, in order: "framework" (documentation generator profiles),
+    /// "semantic" (articleBody/role=main), "density" (the container with the most prose and fewest
+    /// links) and "readability". Default ["framework", "semantic", "density", "readability"]; e.g.
+    /// ["readability"] forces one tier. The whole page is the last resort either way
+    pub strategy: Option<Vec<ExtractionStrategy>>,
+    /// Re-navigate to the page's canonical URL when it differs from the loaded URL (one hop only, and
+    /// only within the same site; canonical links to other hosts are ignored)
+    #[serde(default)]
+    pub follow_canonical: bool,
+    /// Convert the whole page to markdown without stripping navigation and other chrome
+    #[serde(default)]
+    pub raw: bool,
+    /// Refuse pages disallowed by the host's robots.txt (default: off for single pages, on for crawl_site)
+    pub respect_robots: Option<bool>,
+    /// Extra HTTP headers sent with every request of the page, e.g. Accept-Language
+    pub headers: Option<BTreeMap<String, String>>,
+    /// User-Agent to present instead of the browser's default
+    pub user_agent: Option<String>,
+    /// Abort image, media and font requests to speed up loading; scripts, XHR and styles still load
+    #[serde(default)]
+    pub block_resources: bool,
+    /// Abort requests whose URL contains any of these substrings, e.g. "googletagmanager.com"
+    pub block_url_patterns: Option<Vec<String>>,
+    /// Browser window size; defaults to 1280x800 so responsive sites render their desktop layout
+    pub viewport: Option<ViewportSize>,
+    /// Emulate a named device ("Desktop", "iPhone 13", "Pixel 7", "iPad Pro 11"): viewport, pixel
+    /// ratio, touch and user agent. An explicit viewport or user_agent still takes precedence
+    pub device: Option<String>,
+    /// Browser locale, e.g. "fr-FR"; sets navigator.language and the Accept-Language header so
+    /// localized sites serve that language
+    pub locale: Option<String>,
+    /// Preferred content language, e.g. "fr" or "pt-BR". Sent as Accept-Language, and on sites
+    /// with language-specific paths or parameters (MDN, Android Developers, docs.python.org) the
+    /// URL is rewritten to that language's version of the page
+    pub lang: Option<String>,
+    /// IANA timezone the page sees, e.g. "Europe/Paris", so rendered timestamps are deterministic
+    pub timezone_id: Option<String>,
+    /// Path to a Playwright storage-state JSON file (cookies and localStorage) to load into the
+    /// browser context, e.g. saved after logging in to an SSO-protected portal
+    pub storage_state: Option<String>,
+    /// HTTP Basic Auth credentials for sites like internal Sphinx docs; user:pass@ in the URL works
+    /// too. Never logged
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Cookies to send, as name to value, e.g. {"session": "abc123"}; simpler than a storage-state
+    /// file when one auth or preference cookie is all a site needs. Scoped to the page's host and
+    /// never logged. Ignored with a persistent browser profile
+    pub cookies: Option<BTreeMap<String, String>>,
+    /// Navigation timeout in milliseconds (default 30000)
+    pub timeout_ms: Option<u64>,
+    /// How long to wait for SPA content to render, in milliseconds (default 15000)
+    pub ready_timeout_ms: Option<u64>,
+    /// CSS selectors that signal the page has rendered once one holds text; replaces the defaults
+    /// (main/article content areas and the React, Vue, Svelte and Angular app roots)
+    pub ready_selectors: Option<Vec<String>>,
+    /// Characters of text a readiness selector must hold before the page counts as ready (default
+    /// 100); raise it for pages whose skeleton loaders already exceed that
+    pub ready_min_text_length: Option<usize>,
+    /// Consecutive polls (250ms apart) the content length must stop changing for before extraction
+    /// (default 3); 0 extracts as soon as the minimum length is reached
+    pub ready_stable_polls: Option<u32>,
+    /// CSS selector of an element whose presence means the page is ready, e.g. ".api-reference-loaded".
+    /// Takes precedence over ready_selectors; if it never appears the page is extracted anyway
+    pub wait_for: Option<String>,
+    /// Navigation event to wait for: "load", "domcontentloaded" (default) or "networkidle" for pages
+    /// whose content arrives via XHR after the DOM is parsed
+    #[serde(default)]
+    pub wait_until: WaitStrategy,
+}
+
+impl ScrapeOptions {
+    // The options as they go into cache keys: serialized, with secrets replaced by their SHA-256,
+    // so the keys (in memory, and in SQLite for sitemap crawls) never hold them in plaintext while
+    // different credentials still get different entries
+    pub fn cache_key(&self) -> Result<String, serde_json::Error> {
+        let mut options = self.clone();
+        options.password = options.password.as_deref().map(digest);
+        if let Some(cookies) = &mut options.cookies {
+            cookies.values_mut().for_each(|value| *value = digest(value));
+        }
+        serde_json::to_string(&options)
+    }
+}
+
+// Hand-written so the Basic Auth password and cookie values never end up in logs
+impl fmt::Debug for ScrapeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScrapeOptions")
+            .field("selector", &self.selector)
+            .field("expand_collapsed", &self.expand_collapsed)
+            .field("inline_frames", &self.inline_frames)
+            .field("disable_shadow_dom", &self.disable_shadow_dom)
+            .field("init_script", &self.init_script)
+            .field("scroll_steps", &self.scroll_steps)
+            .field("scroll_delay_ms", &self.scroll_delay_ms)
+            .field("extra_exclusions", &self.extra_exclusions)
+            .field("disable_default_exclusions", &self.disable_default_exclusions)
+            .field("strategy", &self.strategy)
+            .field("follow_canonical", &self.follow_canonical)
+            .field("raw", &self.raw)
+            .field("respect_robots", &self.respect_robots)
+            .field("headers", &self.headers)
+            .field("user_agent", &self.user_agent)
+            .field("block_resources", &self.block_resources)
+            .field("block_url_patterns", &self.block_url_patterns)
+            .field("viewport", &self.viewport)
+            .field("device", &self.device)
+            .field("locale", &self.locale)
+            .field("lang", &self.lang)
+            .field("timezone_id", &self.timezone_id)
+            .field("storage_state", &self.storage_state)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("cookies", &self.cookies.as_ref().map(|cookies| cookies.keys().collect::<Vec<_>>()))
+            .field("timeout_ms", &self.timeout_ms)
+            .field("ready_timeout_ms", &self.ready_timeout_ms)
+            .field("ready_selectors", &self.ready_selectors)
+            .field("ready_min_text_length", &self.ready_min_text_length)
+            .field("ready_stable_polls", &self.ready_stable_polls)
+            .field("wait_for", &self.wait_for)
+            .field("wait_until", &self.wait_until)
+            .finish()
+    }
+}
+
+fn digest(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ViewportSize {
+    pub width: u32,
+    pub height: u32,
+    /// Device pixel ratio (default 1)
+    pub device_scale_factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractionStrategy {
+    Framework,
+    Semantic,
+    Density,
+    Readability,
+}
+
+impl ExtractionStrategy {
+    pub const DEFAULT_ORDER: &[ExtractionStrategy] = &[
+        ExtractionStrategy::Framework,
+        ExtractionStrategy::Semantic,
+        ExtractionStrategy::Density,
+        ExtractionStrategy::Readability,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WaitStrategy {
+    Load,
+    #[default]
+    DomContentLoaded,
+    NetworkIdle,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrapedPage {
+    /// The URL that was requested
+    pub url: String,
+    /// The canonical URL the content was taken from, when it was followed
+    pub canonical_url: Option<String>,
+    pub markdown: String,
+    /// SHA-256 of the markdown, hex-encoded, for spotting the same content under different URLs
+    pub content_hash: String,
+    /// The extracted HTML the markdown was converted from, with absolute URLs; None for text and
+    /// JSON responses, which are not converted. Only returned with include_html
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(flatten)]
+    pub metadata: PageMetadata,
+    /// The HTTP response the content came from
+    pub response: ResponseInfo,
+    /// Heading outline, only filled in when include_toc is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toc: Option<Vec<TocEntry>>,
+    /// Structured content, only filled in for output_format "json"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<ContentBlock>>,
+    /// How the content was isolated, e.g. "framework:Docusaurus v2/v3", "selector:#api", "readability"
+    /// or "full_page", with a "#anchor" suffix when the page was narrowed to the section its URL
+    /// fragment points at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extraction: Option<String>,
+    /// Article title, byline and excerpt, when the readability tier produced the content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reader: Option<ReaderMetadata>,
+    /// Outbound links found on the page, used to drive site crawls
+    #[serde(skip_serializing)]
+    pub links: Vec<Link>,
+}
+
+// A heading of the page outline with the headings nested under it
+#[derive(Debug, Clone, Serialize)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// Fragment that links to the heading, without the '#'
+    pub anchor: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TocEntry>,
+}
+
+// One structural unit of a page for output_format "json", tagged by "type"
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Heading {
+        level: u8,
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+    },
+    Paragraph {
+        text: String,
+    },
+    Code {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+        code: String,
+    },
+    List {
+        ordered: bool,
+        items: Vec<String>,
+    },
+    Table {
+        rows: Vec<Vec<String>>,
+    },
+    Quote {
+        text: String,
+    },
+}
+
+// Final HTTP response of a scrape, after redirects. A final_url that differs from the requested one
+// (e.g. a login page) is the usual sign the content isn't what was asked for.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseInfo {
+    pub status: u16,
+    /// The URL after redirects; relative links and images in the content were resolved against it
+    pub final_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+// What the readability tier reports about the article besides its content
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReaderMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+}
+
+// Document-level metadata read from the page's <head>
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The canonical URL the page declares, whether or not it was followed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_title: Option<String>,
+    /// ISO 639-1 code from <html lang>, or detected from the text when the page doesn't declare one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+// Per-URL entry of a batch crawl: either the markdown or the reason it failed. A page whose
+// content repeats an earlier one carries duplicate_of instead of the markdown.
+#[derive(Debug, Serialize)]
+pub struct CrawlOutcome {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// One page of a site crawl, with the link depth it was discovered at
+#[derive(Debug, Serialize)]
+pub struct SitePage {
+    pub url: String,
+    pub depth: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// URL of the earlier page with identical content; the markdown is left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the start page links to this page, used to order the combined document
+    #[serde(skip_serializing)]
+    pub nav_position: Option<usize>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SearchAndroidRequest {
     pub query: String,
+    /// Number of result pages to collect, 1 to DOCSER_MAX_SEARCH_PAGES (default 1)
+    pub max_page: Option<u32>,
+    /// Skip the search cache and fetch fresh results
+    #[serde(default)]
+    pub refresh: bool,
+    /// Return an empty result with a note instead of an error when the search finds nothing
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// Preferred result language, e.g. "fr"; sent as Accept-Language and used for the results
+    /// page on sites with language-specific URLs
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchDocsRequest {
+    /// Site to search, e.g. "android", "mdn" or "rust-std"
+    pub site: String,
+    pub query: String,
+    /// Number of result pages to collect, 1 to DOCSER_MAX_SEARCH_PAGES (default 1)
     pub max_page: Option<u32>,
+    /// Skip the search cache and fetch fresh results
+    #[serde(default)]
+    pub refresh: bool,
+    /// Return an empty result with a note instead of an error when the search finds nothing
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// Preferred result language, e.g. "fr"; sent as Accept-Language and used for the results
+    /// page on sites with language-specific URLs
+    pub lang: Option<String>,
+}
+
+// Result of the health_check tool
+#[derive(Debug, Serialize)]
+pub struct HealthStatus {
+    /// Whether a page could be opened in the browser
+    pub operational: bool,
+    pub engine: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+// Result of the capabilities tool: what docser knows how to handle, so a client can decide
+// whether a URL is a good fit before crawling it
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    /// Documentation frameworks with a dedicated extraction profile, custom profiles first
+    pub frameworks: Vec<&'static str>,
+    pub search_sites: Vec<SearchSiteInfo>,
+    pub output_formats: Vec<OutputFormat>,
+    pub engines: Vec<String>,
+    /// The engine this server was started with
+    pub engine: String,
+    pub markdown_converters: Vec<String>,
+    /// Names accepted by the device option
+    pub devices: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchSiteInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+// Result of the stats tool: counters since the server started
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub uptime_secs: u64,
+    /// scrape_page calls, page cache hits included
+    pub scrapes: u64,
+    pub scrape_failures: u64,
+    pub page_cache_hits: u64,
+    /// Navigation-to-markdown runs, retries included, and their timing
+    pub scrape_attempts: u64,
+    pub avg_scrape_ms: u64,
+    pub max_scrape_ms: u64,
+    pub scrape_duration_histogram: Vec<DurationBucket>,
+    /// Searches, cache hits included
+    pub searches: u64,
+    pub search_failures: u64,
+    pub search_cache_hits: u64,
+    /// Failed scrapes and searches by error kind, e.g. "timeout" or "http_status"
+    pub failures_by_kind: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DurationBucket {
+    /// Upper bound of the bucket; null for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
 }
 
 #[derive(Serialize)]
 pub struct SearchResult {
     pub links: Vec<Link>,
+    /// Why links is empty, when an empty result was allowed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Link {
     pub href: String,
     pub text: String,
+    /// Result description shown by the search engine, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+use url::Url;
+
+// Spaces out navigations to the same host so bulk crawls don't trip WAFs; hosts are
+// independent. Each caller reserves the next free slot for its host and sleeps until then.
+pub struct HostRateLimiter {
+    // Minimum gap between two navigations to one host; zero disables the limiter
+    interval: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostRateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        let interval = if requests_per_second > 0.0 {
+            Duration::try_from_secs_f64(1.0 / requests_per_second).unwrap_or(Duration::ZERO)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            interval,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn wait(&self, url: &str) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+            return;
+        };
+
+        let now = Instant::now();
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            // Hosts idle for a while have slots in the past; drop them so the map stays small
+            next_slot.retain(|_, slot| *slot + self.interval > now);
+            let slot = next_slot.get(&host).copied().unwrap_or(now).max(now);
+            next_slot.insert(host.clone(), slot + self.interval);
+            slot
+        };
+
+        if slot > now {
+            debug!("Rate limiting {}, waiting {}ms", host, (slot - now).as_millis());
+            tokio::time::sleep_until(slot.into()).await;
+        }
+    }
+}
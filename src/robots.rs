@@ -0,0 +1,227 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+use tracing::{debug, warn};
+
+// Product token matched against User-agent groups in robots.txt
+const ROBOTS_USER_AGENT: &str = "docser";
+
+struct Rule {
+    allow: bool,
+    // Length of the original pattern; the longest matching rule wins
+    specificity: usize,
+    pattern: Regex,
+}
+
+// The rules of the robots.txt group that applies to docser
+#[derive(Default)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+impl RobotsRules {
+    pub fn parse(body: &str) -> Self {
+        // Groups are runs of User-agent lines followed by their rules
+        let mut groups: Vec<(Vec<String>, Vec<(bool, String)>)> = Vec::new();
+        let mut in_agents = false;
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = field.trim().to_lowercase();
+            let value = value.trim().to_string();
+
+            match field.as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_agents = false;
+                    if let Some(group) = groups.last_mut() {
+                        group.1.push((field == "allow", value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Every group naming docser applies, merged as RFC 9309 requires; only without one do
+        // the * groups apply. Product tokens compare exactly, so "User-agent: doc" is not us.
+        let names = |agents: &[String], name: &str| agents.iter().any(|agent| agent == name);
+        let agent = if groups.iter().any(|(agents, _)| names(agents, ROBOTS_USER_AGENT)) {
+            ROBOTS_USER_AGENT
+        } else {
+            "*"
+        };
+
+        let rules = groups
+            .iter()
+            .filter(|(agents, _)| names(agents, agent))
+            .flat_map(|(_, rules)| rules)
+            // An empty Disallow means "allow everything"
+            .filter(|(_, pattern)| !pattern.is_empty())
+            .filter_map(|(allow, pattern)| {
+                Some(Rule {
+                    allow: *allow,
+                    specificity: pattern.len(),
+                    pattern: pattern_to_regex(pattern)?,
+                })
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    // Longest matching rule decides; on a tie Allow wins, and no match means allowed
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(path))
+            .max_by_key(|rule| (rule.specificity, rule.allow))
+            .map(|rule| rule.allow)
+            .unwrap_or(true)
+    }
+}
+
+// robots.txt patterns are path prefixes with * wildcards and an optional trailing $ anchor
+fn pattern_to_regex(pattern: &str) -> Option<Regex> {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(stripped) => (stripped, true),
+        None => (pattern, false),
+    };
+    let mut regex = String::from("^");
+    regex.push_str(&pattern.split('*').map(regex::escape).collect::<Vec<_>>().join(".*"));
+    if anchored {
+        regex.push('$');
+    }
+    Regex::new(&regex).ok()
+}
+
+// Fetches robots.txt once per origin and keeps the parsed rules for the process lifetime
+pub struct RobotsCache {
+    client: reqwest::Client,
+    rules: Mutex<HashMap<String, Arc<RobotsRules>>>,
+}
+
+impl RobotsCache {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            rules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn is_allowed(&self, url: &Url) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let cached = self.rules.lock().unwrap_or_else(|e| e.into_inner()).get(&origin).cloned();
+        let rules = match cached {
+            Some(rules) => rules,
+            None => {
+                let rules = Arc::new(self.fetch(&origin).await);
+                self.rules
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(origin, rules.clone());
+                rules
+            }
+        };
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        rules.is_allowed(&path)
+    }
+
+    // A missing or unreachable robots.txt allows everything
+    async fn fetch(&self, origin: &str) -> RobotsRules {
+        let robots_url = format!("{}/robots.txt", origin);
+        let response = self
+            .client
+            .get(&robots_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(body) => {
+                    debug!("Loaded {}", robots_url);
+                    RobotsRules::parse(&body)
+                }
+                Err(e) => {
+                    warn!("Failed to read {}: {}", robots_url, e);
+                    RobotsRules::default()
+                }
+            },
+            Ok(response) => {
+                debug!("No robots.txt at {} (HTTP {})", robots_url, response.status());
+                RobotsRules::default()
+            }
+            Err(e) => {
+                warn!("Failed to fetch {}: {}", robots_url, e);
+                RobotsRules::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docser_group_replaces_the_wildcard_group() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /\n\nUser-agent: Docser\nDisallow: /private\n");
+        assert!(rules.is_allowed("/guide"));
+        assert!(!rules.is_allowed("/private/page"));
+    }
+
+    #[test]
+    fn partial_agent_names_do_not_match() {
+        let rules = RobotsRules::parse("User-agent: doc\nDisallow: /\n\nUser-agent: *\nDisallow: /tmp\n");
+        assert!(rules.is_allowed("/guide"));
+        assert!(!rules.is_allowed("/tmp/file"));
+    }
+
+    #[test]
+    fn groups_naming_docser_are_merged() {
+        let rules = RobotsRules::parse("User-agent: docser\nDisallow: /a\n\nUser-agent: other\nDisallow: /\n\nUser-agent: docser\nDisallow: /b\n");
+        assert!(!rules.is_allowed("/a/1"));
+        assert!(!rules.is_allowed("/b/1"));
+        assert!(rules.is_allowed("/c"));
+    }
+
+    #[test]
+    fn empty_docser_group_allows_everything() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /\n\nUser-agent: docser\nDisallow:\n");
+        assert!(rules.is_allowed("/anything"));
+    }
+
+    #[test]
+    fn longest_match_wins_and_allow_wins_ties() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /docs\nAllow: /docs/public\nDisallow: /page\nAllow: /page\n");
+        assert!(!rules.is_allowed("/docs/private"));
+        assert!(rules.is_allowed("/docs/public/intro"));
+        assert!(rules.is_allowed("/page"));
+    }
+
+    #[test]
+    fn wildcard_and_end_anchor_patterns() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /*.pdf$\nDisallow: /search*q=\n");
+        assert!(!rules.is_allowed("/files/guide.pdf"));
+        assert!(rules.is_allowed("/files/guide.pdf?download=1"));
+        assert!(!rules.is_allowed("/search?page=2&q=rust"));
+        assert!(rules.is_allowed("/search"));
+    }
+}
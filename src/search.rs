@@ -0,0 +1,122 @@
+use crate::constants::js_string;
+
+// Site-specific search integrations. Each site describes where its results page lives
+// and how to find result links on it, so the browser side can stay generic.
+pub struct SearchSite {
+    pub name: &'static str,
+    pub description: &'static str,
+    // Results page URL with a {query} placeholder
+    pub results_url: &'static str,
+    // Element whose presence means the results have rendered
+    pub ready_selector: &'static str,
+    pub link_selector: &'static str,
+    // Broader selector tried when link_selector finds nothing
+    pub fallback_link_selector: Option<&'static str>,
+    // Element wrapping one result (the link itself when None) and the snippet text inside it
+    pub result_selector: Option<&'static str>,
+    pub snippet_selector: Option<&'static str>,
+    // Only links starting with this prefix are kept
+    pub href_prefix: &'static str,
+    // Whether results are paginated with Google Programmable Search (.gsc-cursor-page)
+    pub google_cse_pagination: bool,
+}
+
+pub const SEARCH_SITES: &[SearchSite] = &[
+    SearchSite {
+        fallback_link_selector: Some(".devsite-article a"),
+        ..SearchSite::google_cse(
+            "android",
+            "Android Developers (developer.android.com)",
+            "https://developer.android.com/s/results?q={query}",
+            "https://developer.android.com/",
+        )
+    },
+    SearchSite {
+        name: "mdn",
+        description: "MDN Web Docs (developer.mozilla.org)",
+        results_url: "https://developer.mozilla.org/en-US/search?q={query}",
+        ready_selector: ".search-results a",
+        link_selector: ".search-results a[href*='/docs/']",
+        fallback_link_selector: Some("main a[href*='/docs/']"),
+        result_selector: None,
+        snippet_selector: None,
+        href_prefix: "https://developer.mozilla.org/",
+        google_cse_pagination: false,
+    },
+    SearchSite {
+        name: "rust-std",
+        description: "Rust standard library (doc.rust-lang.org/std)",
+        results_url: "https://doc.rust-lang.org/std/index.html?search={query}",
+        ready_selector: ".search-results a",
+        link_selector: ".search-results a",
+        fallback_link_selector: None,
+        result_selector: None,
+        snippet_selector: Some(".desc"),
+        href_prefix: "https://doc.rust-lang.org/",
+        google_cse_pagination: false,
+    },
+];
+
+impl SearchSite {
+    // A site whose search page embeds Google Programmable Search: the result markup, snippets and
+    // pagination are the same everywhere, so only the results URL and the link filter differ
+    pub const fn google_cse(
+        name: &'static str,
+        description: &'static str,
+        results_url: &'static str,
+        href_prefix: &'static str,
+    ) -> SearchSite {
+        SearchSite {
+            name,
+            description,
+            results_url,
+            ready_selector: ".gs-title",
+            link_selector: ".gsc-webResult.gsc-result .gs-webResult .gs-title a",
+            fallback_link_selector: None,
+            result_selector: Some(".gsc-webResult"),
+            snippet_selector: Some(".gs-snippet"),
+            href_prefix,
+            google_cse_pagination: true,
+        }
+    }
+
+    pub fn find(name: &str) -> Option<&'static SearchSite> {
+        SEARCH_SITES.iter().find(|site| site.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn android() -> &'static SearchSite {
+        &SEARCH_SITES[0]
+    }
+
+    pub fn results_url_for(&self, query: &str) -> String {
+        self.results_url.replace("{query}", &urlencoding::encode(query))
+    }
+
+    pub fn ready_js(&self) -> String {
+        format!("document.querySelector({})", js_string(self.ready_selector))
+    }
+
+    // JS returning a JSON array of {href, text, snippet} for every element matching the selector.
+    // snippet is null when the site has no snippet selector or the result has no snippet.
+    pub fn links_js(&self, selector: &str) -> String {
+        let snippet_js = match self.snippet_selector {
+            Some(snippet_selector) => {
+                let container = match self.result_selector {
+                    Some(result_selector) => format!("(a.closest({}) || a)", js_string(result_selector)),
+                    None => "a".to_string(),
+                };
+                format!(
+                    "{}.querySelector({})?.textContent.trim() || null",
+                    container,
+                    js_string(snippet_selector)
+                )
+            }
+            None => "null".to_string(),
+        };
+        format!(
+            "JSON.stringify(Array.from(document.querySelectorAll({})).map(a => ({{href: a.href, text: a.textContent.trim(), snippet: {}}})))",
+            js_string(selector),
+            snippet_js
+        )
+    }
+}
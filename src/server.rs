@@ -1,12 +1,23 @@
 use rmcp::{
     ErrorData as McpError, ServerHandler,
     handler::server::router::tool::ToolRouter,
+    handler::server::tool::cached_schema_for_type,
     handler::server::wrapper::Parameters,
     model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
     tool, tool_handler, tool_router,
 };
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use crate::browser::BrowserManager;
-use crate::models::{CrawlUrlRequest, SearchAndroidRequest};
+use crate::error::DocserError;
+use crate::extractor;
+use crate::models::{
+    ContentBlock, CrawlOutcome, CrawlResult, CrawlSiteRequest, CrawlSitemapRequest, CrawlUrlRequest, CrawlUrlsRequest,
+    DetectFrameworkRequest, ExtractHtmlRequest, ExtractLinksRequest, ExtractedContent, ExtractionStrategy,
+    FetchHtmlRequest, OutputFormat, PageContent, RenderPdfRequest, ScrapedPage, SearchAndroidRequest, SearchDocsRequest,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::search::{SEARCH_SITES, SearchSite};
+use crate::urls::PathFilter;
 
 #[derive(Clone)]
 pub struct SimpleServer {
@@ -15,25 +26,243 @@ pub struct SimpleServer {
 }
 
 impl SimpleServer {
-    pub async fn new() -> Self {
-        Self {
+    pub async fn try_new() -> Result<Self, DocserError> {
+        Ok(Self {
             tool_router: Self::tool_router(),
-            browser: BrowserManager::new().await,
-        }
+            browser: BrowserManager::try_new().await?,
+        })
+    }
+
+    pub async fn shutdown(&self) {
+        self.browser.shutdown().await;
     }
 }
 
 #[tool_router]
 impl SimpleServer {
-    #[tool(description = "Crawls a URL and converts the content to markdown")]
+    #[tool(
+        description = "Crawls a URL and converts the content to markdown, to plain text (output_format \"text\") or to a JSON array of typed blocks (\"json\"). A URL fragment (#installation) limits the output to that section of the page. Structured content carries {url, title, byline, excerpt, markdown, word_count, approx_tokens}, with byline and excerpt filled in when the readability fallback extracted the page",
+        output_schema = cached_schema_for_type::<CrawlResult>()
+    )]
     async fn crawl_url(
         &self,
         Parameters(request): Parameters<CrawlUrlRequest>,
     ) -> Result<CallToolResult, McpError> {
-        match self.browser.scrape_page(&request.url).await {
-            Ok(markdown) => Ok(CallToolResult::success(vec![Content::text(markdown)])),
-            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!("Error: {}", e))])),
+        let mut page = self.browser.scrape_page(&request.url, &request.options).await?;
+        // Text and JSON responses were never HTML, so their body is already as plain as it gets
+        match (request.output_format, &page.html) {
+            (OutputFormat::Text, Some(html)) => page.markdown = extractor::to_text(html),
+            (OutputFormat::Json, html) => {
+                page.blocks = Some(match html {
+                    Some(html) => extractor::to_blocks(html),
+                    None => vec![ContentBlock::Paragraph { text: page.markdown.clone() }],
+                });
+            }
+            _ => {}
+        }
+        let mut link_references = None;
+        if request.output_format != OutputFormat::Text {
+            let (markdown, references) = extractor::restyle_links(&page.markdown, request.links);
+            page.markdown = markdown;
+            link_references = Some(references);
+        }
+        if request.include_toc {
+            page.toc = Some(page.html.as_deref().map(extractor::table_of_contents).unwrap_or_default());
+        }
+        if let Some(max_chars) = request.max_chars {
+            extractor::truncate_chars(&mut page.markdown, max_chars);
+            if let Some(blocks) = &mut page.blocks {
+                extractor::truncate_blocks(blocks, max_chars);
+            }
+        }
+        // Footnote URLs go after the cut, and only for the references that survived it
+        if let Some(references) = link_references {
+            references.append_to(&mut page.markdown);
+        }
+        if !request.include_html {
+            page.html = None;
+        }
+        let reader = page.reader.clone().unwrap_or_default();
+        let structured = CrawlResult {
+            url: page.url.clone(),
+            title: page.metadata.title.clone().or(reader.title),
+            byline: reader.byline,
+            excerpt: reader.excerpt,
+            word_count: extractor::word_count(&page.markdown),
+            approx_tokens: extractor::approx_tokens(&page.markdown),
+            markdown: page.markdown.clone(),
+        };
+
+        let contents = if request.include_metadata {
+            vec![Content::json(&page)?]
+        } else {
+            let mut contents = vec![match (request.include_html, &page.blocks) {
+                (true, _) => Content::json(&PageContent {
+                    markdown: page.markdown,
+                    html: page.html,
+                    blocks: page.blocks.clone(),
+                })?,
+                (false, Some(blocks)) => Content::json(blocks)?,
+                (false, None) => Content::text(page.markdown),
+            }];
+            // The outline goes in a second content item so the first stays plain markdown
+            if let Some(toc) = &page.toc {
+                contents.push(Content::json(toc)?);
+            }
+            contents
+        };
+        // The content items stay as they were for clients that don't read structured content
+        let mut result = CallToolResult::success(contents);
+        result.structured_content = Some(serde_json::to_value(&structured).map_err(DocserError::from)?);
+        Ok(result)
+    }
+
+    #[tool(description = "Crawls several URLs concurrently and returns a JSON map of URL to markdown or error")]
+    async fn crawl_urls(
+        &self,
+        Parameters(request): Parameters<CrawlUrlsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if request.urls.is_empty() {
+            return Err(DocserError::InvalidArgument("urls must not be empty".into()).into());
+        }
+
+        // The response is keyed by URL, so a URL listed twice is only scraped once
+        let mut listed = HashSet::new();
+        let urls: Vec<String> = request.urls.into_iter().filter(|url| listed.insert(url.clone())).collect();
+        let results = self.browser.scrape_pages(&urls, &request.options).await;
+        crawl_outcomes(results)
+    }
+
+    #[tool(description = "Crawls the pages listed in a sitemap.xml (following sitemap indexes), optionally filtered by URL prefix or glob and capped by max_pages, and returns a JSON map of URL to markdown or error")]
+    async fn crawl_sitemap(
+        &self,
+        Parameters(request): Parameters<CrawlSitemapRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let max_pages = request.max_pages.unwrap_or(50);
+        if max_pages == 0 {
+            return Err(DocserError::InvalidArgument("max_pages must be at least 1".into()).into());
+        }
+
+        let results = self
+            .browser
+            .crawl_sitemap(
+                &request.url,
+                request.url_prefix.as_deref(),
+                request.url_pattern.as_deref(),
+                max_pages,
+                request.force,
+                &request.options,
+            )
+            .await?;
+        crawl_outcomes(results)
+    }
+
+    #[tool(description = "Crawls a site from a start URL, following same-host links up to max_depth and max_pages (optionally only those whose path matches include_patterns and none of exclude_patterns), and returns a JSON list of {url, depth, title, markdown}, or with combined a single markdown document of every page")]
+    async fn crawl_site(
+        &self,
+        Parameters(request): Parameters<CrawlSiteRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let max_depth = request.max_depth.unwrap_or(2);
+        let max_pages = request.max_pages.unwrap_or(20);
+        if max_pages == 0 {
+            return Err(DocserError::InvalidArgument("max_pages must be at least 1".into()).into());
+        }
+
+        let filter = PathFilter::new(
+            request.include_patterns.as_deref().unwrap_or_default(),
+            request.exclude_patterns.as_deref().unwrap_or_default(),
+        )?;
+
+        let pages = self.browser.crawl_site(&request.url, max_depth, max_pages, &filter, &request.options).await?;
+        if request.combined {
+            return Ok(CallToolResult::success(vec![Content::text(extractor::combine_pages(&pages))]));
+        }
+        let json = serde_json::to_string(&pages).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Returns the outbound links of a page as a JSON array of {href, text}, with absolute URLs, optionally limited to the page's own host")]
+    async fn extract_links(
+        &self,
+        Parameters(request): Parameters<ExtractLinksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let links = self
+            .browser
+            .page_links(&request.url, &request.options, request.same_domain)
+            .await?;
+        let json = serde_json::to_string(&links).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Returns the rendered HTML of a page without content extraction or markdown conversion: open shadow roots expanded, scripts and styles removed, after the same readiness wait as crawl_url")]
+    async fn fetch_html(
+        &self,
+        Parameters(request): Parameters<FetchHtmlRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let html = self.browser.fetch_html(&request.url, &request.options).await?;
+        Ok(CallToolResult::success(vec![Content::text(html)]))
+    }
+
+    #[tool(description = "Detects which documentation generator a page was built with (e.g. \"Docusaurus v2/v3\", \"MkDocs (Material)\") from its layout; returns the framework name or \"unknown\"")]
+    async fn detect_framework(
+        &self,
+        Parameters(request): Parameters<DetectFrameworkRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let html = self.browser.fetch_html(&request.url, &request.options).await?;
+        let framework = extractor::detect_framework(&html).unwrap_or("unknown");
+        Ok(CallToolResult::success(vec![Content::text(framework)]))
+    }
+
+    #[tool(description = "Runs content extraction over raw HTML without a browser and returns {markdown, extraction} as JSON, where extraction names the framework profile or tier used")]
+    async fn extract_html(
+        &self,
+        Parameters(request): Parameters<ExtractHtmlRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if request.html.trim().is_empty() {
+            return Err(DocserError::InvalidArgument("html must not be empty".into()).into());
         }
+        let exclusions = extractor::exclusion_selectors(
+            request.extra_exclusions.as_deref().unwrap_or_default(),
+            !request.disable_default_exclusions,
+        );
+        let strategy = request.strategy.as_deref().unwrap_or(ExtractionStrategy::DEFAULT_ORDER);
+        let (markdown, tier) = self
+            .browser
+            .extract_html(&request.html, request.url.as_deref(), &exclusions, strategy);
+        let result = ExtractedContent { markdown, extraction: tier.to_string(), reader: tier.into_reader() };
+        let json = serde_json::to_string(&result).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Renders a page to PDF (A4 or Letter) and returns it base64-encoded. Requires the chromium engine")]
+    async fn render_pdf(
+        &self,
+        Parameters(request): Parameters<RenderPdfRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let pdf = self
+            .browser
+            .render_pdf(&request.url, request.format, request.print_background, &request.options)
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(BASE64.encode(pdf))]))
+    }
+
+    #[tool(description = "Checks that the browser backend launches and can open a page; returns {operational, engine, version, error, elapsed_ms} as JSON")]
+    async fn health_check(&self) -> Result<CallToolResult, McpError> {
+        let status = self.browser.health_check().await;
+        let json = serde_json::to_string(&status).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Returns scrape and search counters since startup as JSON: totals, cache hits, failures by error kind, and average, maximum and histogram of scrape durations")]
+    async fn stats(&self) -> Result<CallToolResult, McpError> {
+        let json = serde_json::to_string(&self.browser.stats()).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Lists what docser supports as JSON: documentation frameworks with dedicated extraction profiles, search_docs sites, output formats, browser engines (and the one in use), markdown converters and emulated devices")]
+    async fn capabilities(&self) -> Result<CallToolResult, McpError> {
+        let json = serde_json::to_string(&self.browser.capabilities()).map_err(DocserError::from)?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
     #[tool(description = "Searches Android Developers")]
@@ -42,11 +271,85 @@ impl SimpleServer {
         Parameters(request): Parameters<SearchAndroidRequest>,
     ) -> Result<CallToolResult, McpError> {
         let max_page = request.max_page.unwrap_or(1);
-        match self.browser.search_android_dev(&request.query, max_page).await {
-            Ok(result) => Ok(CallToolResult::success(vec![Content::text(result)])),
-            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!("Error: {}", e))])),
+        let result = self
+            .browser
+            .search_android_dev(&request.query, max_page, request.refresh, request.allow_empty, request.lang.as_deref())
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(description = "Searches a documentation site (android, mdn, rust-std) and returns result links as JSON")]
+    async fn search_docs(
+        &self,
+        Parameters(request): Parameters<SearchDocsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let site = SearchSite::find(&request.site).ok_or_else(|| {
+            let known: Vec<String> = SEARCH_SITES
+                .iter()
+                .map(|site| format!("{} ({})", site.name, site.description))
+                .collect();
+            DocserError::InvalidArgument(format!(
+                "Unknown search site '{}', expected one of: {}",
+                request.site,
+                known.join(", ")
+            ))
+        })?;
+        let max_page = request.max_page.unwrap_or(1);
+        let result = self
+            .browser
+            .search_site(
+                site,
+                &request.query,
+                max_page,
+                request.refresh,
+                request.allow_empty,
+                request.lang.as_deref(),
+            )
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+}
+
+// JSON map of URL to markdown or error, shared by the batch crawl tools
+// Pages whose content repeats an earlier page in the batch (print versions, trailing-slash and
+// locale variants) are reported by hash with duplicate_of instead of repeating the markdown
+fn crawl_outcomes(results: Vec<(String, Result<ScrapedPage, DocserError>)>) -> Result<CallToolResult, McpError> {
+    let json = serde_json::to_string(&outcomes_by_url(results)).map_err(DocserError::from)?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+// A URL that appears more than once keeps its first result, so it is never reported as a
+// duplicate of itself
+fn outcomes_by_url(results: Vec<(String, Result<ScrapedPage, DocserError>)>) -> BTreeMap<String, CrawlOutcome> {
+    let mut first_with_hash: HashMap<String, String> = HashMap::new();
+    let mut outcomes: BTreeMap<String, CrawlOutcome> = BTreeMap::new();
+    for (url, result) in results {
+        if outcomes.contains_key(&url) {
+            continue;
         }
+        let outcome = match result {
+            Ok(page) => match first_with_hash.get(&page.content_hash) {
+                Some(original) => CrawlOutcome {
+                    markdown: None,
+                    content_hash: Some(page.content_hash),
+                    duplicate_of: Some(original.clone()),
+                    error: None,
+                },
+                None => {
+                    first_with_hash.insert(page.content_hash.clone(), url.clone());
+                    CrawlOutcome {
+                        markdown: Some(page.markdown),
+                        content_hash: Some(page.content_hash),
+                        duplicate_of: None,
+                        error: None,
+                    }
+                }
+            },
+            Err(e) => CrawlOutcome { markdown: None, content_hash: None, duplicate_of: None, error: Some(e.to_string()) },
+        };
+        outcomes.insert(url, outcome);
     }
+    outcomes
 }
 
 #[tool_handler]
@@ -57,4 +360,52 @@ impl ServerHandler for SimpleServer {
             ..Default::default()
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PageMetadata, ResponseInfo};
+
+    fn scraped(url: &str, markdown: &str) -> (String, Result<ScrapedPage, DocserError>) {
+        let page = ScrapedPage {
+            url: url.to_string(),
+            canonical_url: None,
+            markdown: markdown.to_string(),
+            content_hash: extractor::content_hash(markdown),
+            html: None,
+            toc: None,
+            blocks: None,
+            metadata: PageMetadata::default(),
+            response: ResponseInfo::default(),
+            extraction: None,
+            reader: None,
+            links: Vec::new(),
+        };
+        (url.to_string(), Ok(page))
+    }
+
+    #[test]
+    fn repeated_url_keeps_its_markdown() {
+        let outcomes = outcomes_by_url(vec![scraped("https://d.dev/a", "Body"), scraped("https://d.dev/a", "Body")]);
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes["https://d.dev/a"];
+        assert_eq!(outcome.markdown.as_deref(), Some("Body"));
+        assert_eq!(outcome.duplicate_of, None);
+    }
+
+    #[test]
+    fn same_content_under_another_url_is_a_duplicate() {
+        let outcomes = outcomes_by_url(vec![
+            scraped("https://d.dev/a", "Body"),
+            scraped("https://d.dev/a/print", "Body"),
+            scraped("https://d.dev/b", "Other"),
+            ("https://d.dev/c".to_string(), Err(DocserError::HttpStatus(404))),
+        ]);
+        let duplicate = &outcomes["https://d.dev/a/print"];
+        assert_eq!(duplicate.markdown, None);
+        assert_eq!(duplicate.duplicate_of.as_deref(), Some("https://d.dev/a"));
+        assert_eq!(outcomes["https://d.dev/b"].markdown.as_deref(), Some("Other"));
+        assert!(outcomes["https://d.dev/c"].error.is_some());
+    }
+}
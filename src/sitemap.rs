@@ -0,0 +1,177 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+use tracing::{debug, warn};
+use crate::error::DocserError;
+
+// Child sitemaps followed from sitemap indexes, so a huge index can't stall a crawl
+const MAX_SITEMAPS: usize = 50;
+
+lazy_static! {
+    static ref SITEMAP_ENTRY: Regex = Regex::new(r"(?is)<sitemap\b[^>]*>(.*?)</sitemap>").unwrap();
+    static ref URL_ENTRY: Regex = Regex::new(r"(?is)<url\b[^>]*>(.*?)</url>").unwrap();
+    static ref LOC: Regex = Regex::new(r"(?is)<loc>\s*(?:<!\[CDATA\[)?(.*?)(?:\]\]>)?\s*</loc>").unwrap();
+    static ref LASTMOD: Regex = Regex::new(r"(?is)<lastmod>\s*(.*?)\s*</lastmod>").unwrap();
+}
+
+// A page listed in a <urlset>
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub loc: String,
+    // Last change, normalized to UTC; None when missing or unparsable
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+// A sitemap is either an index of further sitemaps or a list of pages
+enum Sitemap {
+    Index(Vec<String>),
+    UrlSet(Vec<SitemapEntry>),
+}
+
+// Sitemaps are simple enough that pulling <loc> and <lastmod> out of each entry is all we need
+fn parse(xml: &str) -> Sitemap {
+    let children: Vec<String> = SITEMAP_ENTRY
+        .captures_iter(xml)
+        .filter_map(|entry| element_text(&LOC, &entry[1]))
+        .collect();
+    if !children.is_empty() {
+        return Sitemap::Index(children);
+    }
+
+    Sitemap::UrlSet(
+        URL_ENTRY
+            .captures_iter(xml)
+            .filter_map(|entry| {
+                Some(SitemapEntry {
+                    loc: element_text(&LOC, &entry[1])?,
+                    lastmod: element_text(&LASTMOD, &entry[1]).and_then(|lastmod| parse_lastmod(&lastmod)),
+                })
+            })
+            .collect(),
+    )
+}
+
+// Reads a W3C datetime as sitemaps write it: a date (2024, 2024-05 or 2024-05-01, taken as
+// midnight UTC) or a date and time with an offset, with or without seconds. Values mixing forms or
+// offsets only compare correctly once converted like this.
+fn parse_lastmod(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    // Minutes-only times (2024-05-01T08:00+02:00); chrono's %:z doesn't read a bare Z
+    let with_offset = match value.strip_suffix('Z') {
+        Some(rest) => format!("{}+00:00", rest),
+        None => value.to_string(),
+    };
+    if let Ok(datetime) = DateTime::parse_from_str(&with_offset, "%Y-%m-%dT%H:%M%:z") {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    let date = match value.len() {
+        4 => format!("{}-01-01", value),
+        7 => format!("{}-01", value),
+        _ => value.to_string(),
+    };
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+fn element_text(pattern: &Regex, xml: &str) -> Option<String> {
+    let text = pattern.captures(xml)?.get(1)?.as_str().trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+// Fetches a sitemap and, for sitemap indexes, the sitemaps it lists, returning every page entry in
+// sitemap order without duplicates. Only the top-level sitemap failing is an error.
+pub async fn fetch_entries(client: &reqwest::Client, sitemap_url: &str) -> Result<Vec<SitemapEntry>, DocserError> {
+    let mut queue = VecDeque::from([sitemap_url.to_string()]);
+    let mut visited = HashSet::new();
+    let mut seen_pages = HashSet::new();
+    let mut entries = Vec::new();
+
+    while let Some(url) = queue.pop_front() {
+        if visited.len() >= MAX_SITEMAPS {
+            warn!("Stopping after {} sitemaps, {} left unread", MAX_SITEMAPS, queue.len() + 1);
+            break;
+        }
+        if !visited.insert(url.clone()) {
+            continue;
+        }
+
+        let xml = match fetch(client, &url).await {
+            Ok(xml) => xml,
+            Err(e) if url == sitemap_url => return Err(e),
+            Err(e) => {
+                warn!("Skipping sitemap {}: {}", url, e);
+                continue;
+            }
+        };
+        match parse(&xml) {
+            Sitemap::Index(children) => {
+                debug!("Sitemap index {} lists {} sitemaps", url, children.len());
+                queue.extend(children);
+            }
+            Sitemap::UrlSet(pages) => {
+                debug!("Sitemap {} lists {} pages", url, pages.len());
+                entries.extend(pages.into_iter().filter(|page| seen_pages.insert(page.loc.clone())));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<String, DocserError> {
+    let response = client
+        .get(url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| DocserError::Navigation(format!("Failed to fetch sitemap {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(DocserError::HttpStatus(response.status().as_u16()));
+    }
+    response
+        .text()
+        .await
+        .map_err(|e| DocserError::Navigation(format!("Failed to read sitemap {}: {}", url, e)))
+}
+
+// Matches a URL against a glob where '*' is any run of characters, anchored at both ends
+pub fn glob_to_regex(glob: &str) -> Result<Regex, DocserError> {
+    let pattern = glob.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    Regex::new(&format!("^{}$", pattern))
+        .map_err(|e| DocserError::InvalidArgument(format!("Invalid URL pattern '{}': {}", glob, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lastmod_forms_compare_in_utc() {
+        let date = parse_lastmod("2024-05-01").unwrap();
+        let later_same_day = parse_lastmod("2024-05-01T08:00:00+02:00").unwrap();
+        assert!(date < later_same_day);
+        assert_eq!(parse_lastmod("2024-05-01T06:00Z"), Some(later_same_day));
+        // Earlier as a string, later in time
+        assert!(parse_lastmod("2024-05-01T09:00:00+05:00").unwrap() < parse_lastmod("2024-05-01T05:00:00+00:00").unwrap());
+    }
+
+    #[test]
+    fn lastmod_reduced_dates() {
+        assert_eq!(parse_lastmod("2024"), parse_lastmod("2024-01-01"));
+        assert_eq!(parse_lastmod("2024-05"), parse_lastmod("2024-05-01"));
+        assert_eq!(parse_lastmod("yesterday"), None);
+    }
+}
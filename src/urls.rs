@@ -0,0 +1,177 @@
+use regex::Regex;
+use url::Url;
+use crate::error::DocserError;
+use crate::sitemap::glob_to_regex;
+
+// File extensions that never hold documentation worth converting to markdown
+const ASSET_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "bmp", "pdf", "zip", "gz", "tgz", "tar",
+    "mp3", "mp4", "webm", "woff", "woff2", "ttf", "otf", "css", "js", "map", "xml", "json",
+];
+
+// Query parameters that only track where a visitor came from; a trailing '*' matches a prefix
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "mc_cid", "mc_eid",
+    "_ga", "_gl", "igshid", "ref_src",
+];
+
+// Canonical form of a URL for cache keys and dedup: no fragment, no trailing slash, no tracking
+// parameters and the remaining query parameters sorted
+pub fn normalize_url(raw: &str, tracking_params: &[String]) -> String {
+    let Ok(mut url) = Url::parse(raw.trim()) else {
+        return raw.trim().split('#').next().unwrap_or("").trim_end_matches('/').to_string();
+    };
+    url.set_fragment(None);
+
+    let path = url.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        url.set_path(path.trim_end_matches('/'));
+    }
+
+    if url.query().is_some() {
+        let mut pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking_param(name, tracking_params))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        // Stable sort keeps the order of repeated names, which some sites give meaning to
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        if pairs.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+    url.to_string()
+}
+
+// Splits user:pass@ out of a URL, returning the URL without them and the decoded credentials
+pub fn take_credentials(raw: &str) -> (String, Option<(String, String)>) {
+    let Ok(mut url) = Url::parse(raw.trim()) else {
+        return (raw.to_string(), None);
+    };
+    if url.username().is_empty() && url.password().is_none() {
+        return (raw.to_string(), None);
+    }
+    let decode = |value: &str| urlencoding::decode(value).map(|v| v.into_owned()).unwrap_or_else(|_| value.to_string());
+    let credentials = (decode(url.username()), decode(url.password().unwrap_or("")));
+    // Only fails for URLs that can't have credentials, which never had any to strip
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    (url.to_string(), Some(credentials))
+}
+
+// The URL as it may appear in logs and spans
+pub fn redact_credentials(raw: &str) -> String {
+    take_credentials(raw).0
+}
+
+fn is_tracking_param(name: &str, tracking_params: &[String]) -> bool {
+    let name = name.to_ascii_lowercase();
+    tracking_params.iter().any(|param| match param.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == *param,
+    })
+}
+
+pub fn is_asset_url(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    let file = path.rsplit('/').next().unwrap_or("");
+    match file.rsplit_once('.') {
+        Some((_, extension)) => ASSET_EXTENSIONS.contains(&extension),
+        None => false,
+    }
+}
+
+// Which discovered links a recursive crawl may queue, by glob on the URL path (e.g. "/blog/*"):
+// a link matching an exclude pattern is skipped, and once include patterns are given only links
+// matching one of them are followed
+#[derive(Debug, Default)]
+pub struct PathFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, DocserError> {
+        let compile = |globs: &[String]| globs.iter().map(|glob| glob_to_regex(glob.trim())).collect::<Result<Vec<_>, _>>();
+        Ok(Self { include: compile(include)?, exclude: compile(exclude)? })
+    }
+
+    pub fn allows(&self, url: &Url) -> bool {
+        let path = url.path();
+        !self.exclude.iter().any(|pattern| pattern.is_match(path))
+            && (self.include.is_empty() || self.include.iter().any(|pattern| pattern.is_match(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(raw: &str) -> String {
+        let defaults: Vec<String> = DEFAULT_TRACKING_PARAMS.iter().map(|param| param.to_string()).collect();
+        normalize_url(raw, &defaults)
+    }
+
+    #[test]
+    fn tracking_parameters_are_dropped() {
+        assert_eq!(
+            normalize("https://docs.dev/guide?utm_source=x&page=2&UTM_Medium=y&fbclid=z"),
+            "https://docs.dev/guide?page=2"
+        );
+    }
+
+    #[test]
+    fn custom_tracking_parameters_replace_the_defaults() {
+        let custom = vec!["ref".to_string(), "src_*".to_string()];
+        assert_eq!(
+            normalize_url("https://docs.dev/a?ref=home&src_id=1&utm_source=x", &custom),
+            "https://docs.dev/a?utm_source=x"
+        );
+    }
+
+    #[test]
+    fn remaining_parameters_are_sorted_keeping_repeats_in_order() {
+        assert_eq!(
+            normalize("https://docs.dev/search?q=rust&tag=b&lang=en&tag=a"),
+            "https://docs.dev/search?lang=en&q=rust&tag=b&tag=a"
+        );
+    }
+
+    #[test]
+    fn fragment_and_trailing_slash_are_dropped() {
+        assert_eq!(normalize("https://docs.dev/guide/#install"), "https://docs.dev/guide");
+        assert_eq!(normalize("https://docs.dev/"), "https://docs.dev/");
+    }
+
+    #[test]
+    fn query_of_only_tracking_parameters_leaves_no_question_mark() {
+        assert_eq!(normalize("https://docs.dev/guide?utm_source=x&gclid=y#top"), "https://docs.dev/guide");
+    }
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let owned = |globs: &[&str]| globs.iter().map(|glob| glob.to_string()).collect::<Vec<_>>();
+        PathFilter::new(&owned(include), &owned(exclude)).unwrap()
+    }
+
+    fn allows(filter: &PathFilter, url: &str) -> bool {
+        filter.allows(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn exclude_patterns_skip_matching_paths() {
+        let filter = filter(&[], &["/blog/*", "*/changelog*"]);
+        assert!(allows(&filter, "https://docs.dev/guide/intro"));
+        assert!(!allows(&filter, "https://docs.dev/blog/2024/release"));
+        assert!(!allows(&filter, "https://docs.dev/api/changelog?page=2"));
+    }
+
+    #[test]
+    fn include_patterns_limit_and_exclude_wins() {
+        let filter = filter(&["/guide/*"], &["/guide/legacy/*"]);
+        assert!(allows(&filter, "https://docs.dev/guide/setup"));
+        assert!(!allows(&filter, "https://docs.dev/reference/setup"));
+        assert!(!allows(&filter, "https://docs.dev/guide/legacy/setup"));
+    }
+}